@@ -0,0 +1,88 @@
+//! Lexing and parsing throughput on a large generated script, with the number of heap
+//! allocations each run makes.
+//!
+//! Run with `cargo bench`. The "owned" case converts every token with `Token::into_owned`, which
+//! is what lexing cost when each token allocated its own literal.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use rustymonkey::lexer::Lexer;
+use rustymonkey::parser::Parser;
+
+const ITERATIONS: u32 = 20;
+
+/// Counts allocations so the benchmark can report them alongside the timings.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn generate_script(functions: usize) -> String {
+    let mut script = String::new();
+    for i in 0..functions {
+        script.push_str(&format!("/// Function number {i}.\n"));
+        script.push_str(&format!("let function_{i} = fn(alpha, beta, gamma) {{\n"));
+        script.push_str("    let total = alpha * beta + gamma - 42 / (alpha + 1);\n");
+        script.push_str(&format!("    if (total > {i}) {{ return \"large value\"; }}\n"));
+        script.push_str("    // a plain comment\n");
+        script.push_str(&format!("    total == function_{i}(beta, gamma, alpha);\n"));
+        script.push_str("};\n");
+    }
+    script
+}
+
+fn bench<F: FnMut()>(name: &str, bytes: usize, mut f: F) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    let mut best = Duration::MAX;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    let throughput = bytes as f64 / best.as_secs_f64() / (1024.0 * 1024.0);
+    println!("{:<16} {:>10.3} ms {:>10.1} MiB/s {:>10} allocations",
+             name, best.as_secs_f64() * 1000.0, throughput, allocations);
+}
+
+fn main() {
+    let script = generate_script(20_000);
+    println!("script: {} bytes\n", script.len());
+
+    bench("lex (borrowed)", script.len(), || {
+        for token in Lexer::new(&script) {
+            black_box(token);
+        }
+    });
+    bench("lex (owned)", script.len(), || {
+        for token in Lexer::new(&script) {
+            black_box(token.into_owned());
+        }
+    });
+    bench("parse", script.len(), || {
+        let mut parser = Parser::new(Lexer::new(&script));
+        black_box(parser.parse_program());
+    });
+}
@@ -1,24 +1,38 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
 use std::fmt::{Display, Formatter, Result};
-use as_any::{AsAny};
-use crate::token::Token;
-use dyn_clone::DynClone;
+use std::rc::Rc;
+use num_bigint::BigInt;
+use crate::token::{Span, Token};
 
 #[derive(PartialEq, Debug)]
 pub enum NodeType {
     Program,
     LetStatement,
+    ConstStatement,
     ReturnStatement,
     ExpressionStatement,
+    WhileStatement,
+    ForStatement,
+    BreakStatement,
+    ContinueStatement,
     Identifier,
     IntegerLiteral,
+    BigIntegerLiteral,
+    FloatLiteral,
     StringLiteral,
     PrefixExpression,
     InfixExpression,
+    LogicalExpression,
     BooleanLiteral,
     BlockStatement,
     IfExpression,
     FunctionLiteral,
     CallExpression,
+    ArrayLiteral,
+    IndexExpression,
+    HashLiteral,
+    AssignExpression,
 }
 
 macro_rules! node_type_fn {
@@ -32,16 +46,23 @@ macro_rules! node_type_fn {
 macro_rules! token_literal_fn {
     () => {
         fn token_literal(&self) -> String {
-            self.token.literal.clone()
+            self.token.literal.to_string()
         }
     };
 }
 
 macro_rules! impl_node {
     ($T:ident,$node_type:expr) => {
-        impl Node for $T {
+        impl_node!($T, $node_type, self => self.token.span);
+    };
+    ($T:ident,$node_type:expr,$self:ident => $span:expr) => {
+        impl Node for $T<'_> {
             node_type_fn!($node_type);
             token_literal_fn!();
+
+            fn span(&$self) -> Span {
+                $span
+            }
         }
     };
 }
@@ -49,45 +70,120 @@ macro_rules! impl_node {
 pub trait Node: Display {
     fn node_type(&self) -> NodeType;
     fn token_literal(&self) -> String;
+    /// The region of source text this node was parsed from.
+    fn span(&self) -> Span;
 }
 
-pub trait Statement: Node + DynClone + AsAny {
-    fn statement_node(&self) {}
-}
-dyn_clone::clone_trait_object!(Statement);
+/// Forwards `Node` and `Display` for an enum whose variants each wrap a single node.
+macro_rules! impl_node_enum {
+    ($T:ident { $($variant:ident),* $(,)? }) => {
+        impl Node for $T<'_> {
+            fn node_type(&self) -> NodeType {
+                match self {
+                    $($T::$variant(node) => node.node_type(),)*
+                }
+            }
+
+            fn token_literal(&self) -> String {
+                match self {
+                    $($T::$variant(node) => node.token_literal(),)*
+                }
+            }
 
-pub fn to_concrete_statement<T: Clone + 'static>(statement: &Box<dyn Statement>) -> Box<T> {
-    let dc = statement.as_ref().as_any().downcast_ref::<T>().unwrap();
-    Box::new(dc.clone())
+            fn span(&self) -> Span {
+                match self {
+                    $($T::$variant(node) => node.span(),)*
+                }
+            }
+        }
+
+        impl Display for $T<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+                match self {
+                    $($T::$variant(node) => write!(f, "{}", node),)*
+                }
+            }
+        }
+    };
 }
 
-pub trait Expression: Node + DynClone + AsAny {
-    fn expression_node(&self) {}
+#[derive(Clone, Debug)]
+pub enum Statement<'src> {
+    Let(LetStatement<'src>),
+    Const(ConstStatement<'src>),
+    Return(ReturnStatement<'src>),
+    Expression(ExpressionStatement<'src>),
+    While(WhileStatement<'src>),
+    For(ForStatement<'src>),
+    Break(BreakStatement<'src>),
+    Continue(ContinueStatement<'src>),
 }
-dyn_clone::clone_trait_object!(Expression);
 
-pub fn to_concrete_expression<T: Clone + 'static>(expression: &Box<dyn Expression>) -> Box<T> {
-    let dc = expression.as_ref().as_any().downcast_ref::<T>().unwrap();
-    Box::new(dc.clone())
+impl_node_enum!(Statement { Let, Const, Return, Expression, While, For, Break, Continue });
+
+#[derive(Clone, Debug)]
+pub enum Expression<'src> {
+    Identifier(Identifier<'src>),
+    IntegerLiteral(IntegerLiteral<'src>),
+    BigIntegerLiteral(BigIntegerLiteral<'src>),
+    FloatLiteral(FloatLiteral<'src>),
+    BooleanLiteral(BooleanLiteral<'src>),
+    StringLiteral(StringLiteral<'src>),
+    Prefix(PrefixExpression<'src>),
+    Infix(InfixExpression<'src>),
+    Logical(LogicalExpression<'src>),
+    If(IfExpression<'src>),
+    FunctionLiteral(FunctionLiteral<'src>),
+    Call(CallExpression<'src>),
+    ArrayLiteral(ArrayLiteral<'src>),
+    Index(IndexExpression<'src>),
+    HashLiteral(HashLiteral<'src>),
+    Assign(AssignExpression<'src>),
 }
 
-pub struct Program {
-    pub statements: Vec<Box<dyn Statement>>,
+impl_node_enum!(Expression {
+    Identifier,
+    IntegerLiteral,
+    BigIntegerLiteral,
+    FloatLiteral,
+    BooleanLiteral,
+    StringLiteral,
+    Prefix,
+    Infix,
+    Logical,
+    If,
+    FunctionLiteral,
+    Call,
+    ArrayLiteral,
+    Index,
+    HashLiteral,
+    Assign,
+});
+
+pub struct Program<'src> {
+    pub statements: Vec<Statement<'src>>,
 }
 
-impl Node for Program {
+impl Node for Program<'_> {
     node_type_fn!(NodeType::Program);
 
     fn token_literal(&self) -> String {
-        if self.statements.len() > 0 {
+        if !self.statements.is_empty() {
             self.statements[0].token_literal()
         } else {
             String::from("")
         }
     }
+
+    fn span(&self) -> Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => Span::default(),
+        }
+    }
 }
 
-impl Display for Program {
+impl Display for Program<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         let mut out = String::new();
         for statement in &self.statements {
@@ -97,7 +193,13 @@ impl Display for Program {
     }
 }
 
-impl Program {
+impl Default for Program<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Program<'_> {
     pub fn new() -> Self {
         Self {
             statements: Vec::new(),
@@ -105,50 +207,69 @@ impl Program {
     }
 }
 
-#[derive(Clone)]
-pub struct Identifier {
-    pub token: Token,
-    pub value: String,
+#[derive(Clone, Debug)]
+pub struct Identifier<'src> {
+    pub token: Token<'src>,
+    pub value: Cow<'src, str>,
 }
 
 impl_node!(Identifier, NodeType::Identifier);
 
-impl Expression for Identifier {}
-
-impl Display for Identifier {
+impl Display for Identifier<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(f, "{}", self.value)
     }
 }
 
-#[derive(Clone)]
-pub struct LetStatement {
-    pub token: Token,
-    pub name: Identifier,
-    pub value: Box<dyn Expression>,
+#[derive(Clone, Debug)]
+pub struct LetStatement<'src> {
+    pub token: Token<'src>,
+    pub name: Identifier<'src>,
+    pub value: Expression<'src>,
+    /// The `///` doc comment lines directly above the statement, if the lexer kept them.
+    pub doc: Option<String>,
 }
 
-impl_node!(LetStatement, NodeType::LetStatement);
-
-impl Statement for LetStatement {}
+impl_node!(LetStatement, NodeType::LetStatement,
+          self => self.token.span.to(self.value.span()));
 
-impl Display for LetStatement {
+impl Display for LetStatement<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(f, "{} {} = {};", self.token_literal(), self.name, self.value)
     }
 }
 
-#[derive(Clone)]
-pub struct ReturnStatement {
-    pub token: Token,
-    pub value: Option<Box<dyn Expression>>,
+/// A binding that cannot be assigned to after it is declared. See `resolver::Resolver`.
+#[derive(Clone, Debug)]
+pub struct ConstStatement<'src> {
+    pub token: Token<'src>,
+    pub name: Identifier<'src>,
+    pub value: Expression<'src>,
+    /// The `///` doc comment lines directly above the statement, if the lexer kept them.
+    pub doc: Option<String>,
 }
 
-impl_node!(ReturnStatement, NodeType::ReturnStatement);
+impl_node!(ConstStatement, NodeType::ConstStatement,
+          self => self.token.span.to(self.value.span()));
 
-impl Statement for ReturnStatement {}
+impl Display for ConstStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{} {} = {};", self.token_literal(), self.name, self.value)
+    }
+}
 
-impl Display for ReturnStatement {
+#[derive(Clone, Debug)]
+pub struct ReturnStatement<'src> {
+    pub token: Token<'src>,
+    pub value: Option<Expression<'src>>,
+}
+
+impl_node!(ReturnStatement, NodeType::ReturnStatement, self => match &self.value {
+    Some(value) => self.token.span.to(value.span()),
+    None => self.token.span,
+});
+
+impl Display for ReturnStatement<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         let mut out = String::new();
         out.push_str(&self.token_literal());
@@ -159,85 +280,610 @@ impl Display for ReturnStatement {
             }
                 .as_str(),
         );
-        out.push_str(";");
+        out.push(';');
         write!(f, "{}", out)
     }
 }
 
-#[derive(Clone)]
-pub struct IntegerLiteral {
-    pub token: Token,
+#[derive(Clone, Debug)]
+pub struct WhileStatement<'src> {
+    pub token: Token<'src>,
+    pub condition: Expression<'src>,
+    pub body: BlockStatement<'src>,
+}
+
+impl_node!(WhileStatement, NodeType::WhileStatement,
+          self => self.token.span.to(self.body.span()));
+
+impl Display for WhileStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "while {} {}", self.condition, self.body)
+    }
+}
+
+/// `for variable in iterable { body }`. The variable is bound in the enclosing scope.
+#[derive(Clone, Debug)]
+pub struct ForStatement<'src> {
+    pub token: Token<'src>,
+    pub variable: Identifier<'src>,
+    pub iterable: Expression<'src>,
+    pub body: BlockStatement<'src>,
+}
+
+impl_node!(ForStatement, NodeType::ForStatement,
+          self => self.token.span.to(self.body.span()));
+
+impl Display for ForStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "for {} in {} {}", self.variable, self.iterable, self.body)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BreakStatement<'src> {
+    pub token: Token<'src>,
+}
+
+impl_node!(BreakStatement, NodeType::BreakStatement);
+
+impl Display for BreakStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{};", self.token_literal())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ContinueStatement<'src> {
+    pub token: Token<'src>,
+}
+
+impl_node!(ContinueStatement, NodeType::ContinueStatement);
+
+impl Display for ContinueStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{};", self.token_literal())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IntegerLiteral<'src> {
+    pub token: Token<'src>,
     pub value: i64,
 }
 
 impl_node!(IntegerLiteral, NodeType::IntegerLiteral);
 
-impl Expression for IntegerLiteral {}
+impl Display for IntegerLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.token.literal)
+    }
+}
+
+/// An integer literal too large for an `i64`.
+#[derive(Clone, Debug)]
+pub struct BigIntegerLiteral<'src> {
+    pub token: Token<'src>,
+    pub value: BigInt,
+}
 
-impl Display for IntegerLiteral {
+impl_node!(BigIntegerLiteral, NodeType::BigIntegerLiteral);
+
+impl Display for BigIntegerLiteral<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(f, "{}", self.token.literal)
     }
 }
 
-#[derive(Clone)]
-pub struct ExpressionStatement {
-    pub token: Token,
-    pub expression: Option<Box<dyn Expression>>,
+#[derive(Clone, Debug)]
+pub struct FloatLiteral<'src> {
+    pub token: Token<'src>,
+    pub value: f64,
 }
 
-impl_node!(ExpressionStatement, NodeType::ExpressionStatement);
+impl_node!(FloatLiteral, NodeType::FloatLiteral);
 
-impl Statement for ExpressionStatement {}
+impl Display for FloatLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.token.literal)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BooleanLiteral<'src> {
+    pub token: Token<'src>,
+    pub value: bool,
+}
 
-impl Display for ExpressionStatement {
+impl_node!(BooleanLiteral, NodeType::BooleanLiteral);
+
+impl Display for BooleanLiteral<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        if self.expression.is_none() {
-            write!(f, "")
-        } else {
-            write!(f, "{}", self.expression.as_ref().unwrap())
+        write!(f, "{}", self.token.literal)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StringLiteral<'src> {
+    pub token: Token<'src>,
+    pub value: Cow<'src, str>,
+}
+
+impl_node!(StringLiteral, NodeType::StringLiteral);
+
+impl Display for StringLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut out = String::from("\"");
+        for ch in self.value.chars() {
+            match ch {
+                '\n' => out.push_str("\\n"),
+                '\t' => out.push_str("\\t"),
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("\\\""),
+                ch if ch.is_control() => out.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+                ch => out.push(ch),
+            }
         }
+        out.push('"');
+        write!(f, "{}", out)
     }
 }
 
-#[derive(Clone)]
-pub struct PrefixExpression {
-    pub token: Token,
-    pub operator: String,
-    pub right: Box<dyn Expression>,
+#[derive(Clone, Debug)]
+pub struct ExpressionStatement<'src> {
+    pub token: Token<'src>,
+    pub expression: Option<Expression<'src>>,
 }
 
-impl_node!(PrefixExpression, NodeType::PrefixExpression);
+impl_node!(ExpressionStatement, NodeType::ExpressionStatement, self => match &self.expression {
+    Some(expression) => expression.span(),
+    None => self.token.span,
+});
+
+impl Display for ExpressionStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.expression {
+            Some(expression) => write!(f, "{}", expression),
+            None => write!(f, ""),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PrefixExpression<'src> {
+    pub token: Token<'src>,
+    pub operator: &'static str,
+    pub right: Box<Expression<'src>>,
+}
 
-impl Expression for PrefixExpression {}
+impl_node!(PrefixExpression, NodeType::PrefixExpression,
+          self => self.token.span.to(self.right.span()));
 
-impl Display for PrefixExpression {
+impl Display for PrefixExpression<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(f, "({}{})", self.operator, self.right)
     }
 }
 
-#[derive(Clone)]
-pub struct InfixExpression {
-    pub token: Token,
-    pub left: Box<dyn Expression>,
-    pub operator: String,
-    pub right: Box<dyn Expression>,
+#[derive(Clone, Debug)]
+pub struct InfixExpression<'src> {
+    pub token: Token<'src>,
+    pub left: Box<Expression<'src>>,
+    pub operator: &'static str,
+    pub right: Box<Expression<'src>>,
 }
 
-impl_node!(InfixExpression, NodeType::InfixExpression);
+impl_node!(InfixExpression, NodeType::InfixExpression,
+          self => self.left.span().to(self.right.span()));
 
-impl Expression for InfixExpression {}
+impl Display for InfixExpression<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "({} {} {})", self.left, self.operator, self.right)
+    }
+}
+
+/// `&&` or `||`. Kept apart from `InfixExpression` because the right operand is only evaluated
+/// when the left one does not already decide the result.
+#[derive(Clone, Debug)]
+pub struct LogicalExpression<'src> {
+    pub token: Token<'src>,
+    pub left: Box<Expression<'src>>,
+    pub operator: &'static str,
+    pub right: Box<Expression<'src>>,
+}
+
+impl_node!(LogicalExpression, NodeType::LogicalExpression,
+          self => self.left.span().to(self.right.span()));
 
-impl Display for InfixExpression {
+impl Display for LogicalExpression<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(f, "({} {} {})", self.left, self.operator, self.right)
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct BlockStatement<'src> {
+    pub token: Token<'src>,
+    pub statements: Vec<Statement<'src>>,
+    /// The span of the closing `}`, or of the whole nested `if` for an `else if`.
+    pub closing: Span,
+}
+
+impl_node!(BlockStatement, NodeType::BlockStatement, self => self.token.span.to(self.closing));
+
+impl Display for BlockStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut out = String::from("{");
+        for statement in &self.statements {
+            out.push(' ');
+            out.push_str(&statement.to_string());
+        }
+        out.push_str(" }");
+        write!(f, "{}", out)
+    }
+}
+
+/// An `if` expression. An `else if` chain is held as an alternative block whose only statement
+/// is the nested `IfExpression`.
+#[derive(Clone, Debug)]
+pub struct IfExpression<'src> {
+    pub token: Token<'src>,
+    pub condition: Box<Expression<'src>>,
+    pub consequence: BlockStatement<'src>,
+    pub alternative: Option<BlockStatement<'src>>,
+}
+
+impl_node!(IfExpression, NodeType::IfExpression, self => match &self.alternative {
+    Some(alternative) => self.token.span.to(alternative.span()),
+    None => self.token.span.to(self.consequence.span()),
+});
+
+impl Display for IfExpression<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "if {} {}", self.condition, self.consequence)?;
+        if let Some(alternative) = &self.alternative {
+            write!(f, " else {}", alternative)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FunctionLiteral<'src> {
+    pub token: Token<'src>,
+    pub parameters: Vec<Identifier<'src>>,
+    pub body: BlockStatement<'src>,
+    /// The copy returned by `shared`, made the first time it is asked for.
+    pub shared: OnceCell<SharedFunction>,
+}
+
+impl_node!(FunctionLiteral, NodeType::FunctionLiteral,
+          self => self.token.span.to(self.body.span()));
+
+impl FunctionLiteral<'_> {
+    /// Returns the parameters and body copied out of the source text. The copy is made once per
+    /// literal, so evaluating the literal again only clones two `Rc`s.
+    pub fn shared(&self) -> SharedFunction {
+        self.shared.get_or_init(|| SharedFunction {
+            parameters: self.parameters.iter().map(Identifier::to_static).collect(),
+            body: Rc::new(self.body.to_static()),
+        }).clone()
+    }
+}
+
+/// A function literal's parameters and body, shared by every function value made from it.
+#[derive(Clone, Debug)]
+pub struct SharedFunction {
+    pub parameters: Rc<[Identifier<'static>]>,
+    pub body: Rc<BlockStatement<'static>>,
+}
+
+impl Display for FunctionLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}({}) {}", self.token_literal(), parameters.join(", "), self.body)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CallExpression<'src> {
+    pub token: Token<'src>,
+    pub function: Box<Expression<'src>>,
+    pub arguments: Vec<Expression<'src>>,
+    /// The span of the closing `)`.
+    pub closing: Span,
+}
+
+impl_node!(CallExpression, NodeType::CallExpression,
+          self => self.function.span().to(self.closing));
+
+impl Display for CallExpression<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let arguments: Vec<String> = self.arguments.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}({})", self.function, arguments.join(", "))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArrayLiteral<'src> {
+    pub token: Token<'src>,
+    pub elements: Vec<Expression<'src>>,
+    /// The span of the closing `]`.
+    pub closing: Span,
+}
+
+impl_node!(ArrayLiteral, NodeType::ArrayLiteral, self => self.token.span.to(self.closing));
+
+impl Display for ArrayLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.to_string()).collect();
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexExpression<'src> {
+    pub token: Token<'src>,
+    pub left: Box<Expression<'src>>,
+    pub index: Box<Expression<'src>>,
+    /// The span of the closing `]`.
+    pub closing: Span,
+}
+
+impl_node!(IndexExpression, NodeType::IndexExpression,
+          self => self.left.span().to(self.closing));
+
+impl Display for IndexExpression<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "({}[{}])", self.left, self.index)
+    }
+}
+
+/// A `{key: value}` literal. The pairs are kept in source order.
+#[derive(Clone, Debug)]
+pub struct HashLiteral<'src> {
+    pub token: Token<'src>,
+    pub pairs: Vec<(Expression<'src>, Expression<'src>)>,
+    /// The span of the closing `}`.
+    pub closing: Span,
+}
+
+impl_node!(HashLiteral, NodeType::HashLiteral, self => self.token.span.to(self.closing));
+
+impl Display for HashLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pairs: Vec<String> = self.pairs.iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect();
+        write!(f, "{{{}}}", pairs.join(", "))
+    }
+}
+
+/// `target = value`, or a compound assignment such as `target += value`. The parser only builds
+/// these with an identifier or index expression as the target.
+#[derive(Clone, Debug)]
+pub struct AssignExpression<'src> {
+    pub token: Token<'src>,
+    pub target: Box<Expression<'src>>,
+    pub operator: &'static str,
+    pub value: Box<Expression<'src>>,
+}
+
+impl_node!(AssignExpression, NodeType::AssignExpression,
+          self => self.target.span().to(self.value.span()));
+
+impl Display for AssignExpression<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "({} {} {})", self.target, self.operator, self.value)
+    }
+}
+
+/// Copies a token for a node that outlives its source. Nodes never hold trivia.
+fn static_token(token: &Token) -> Token<'static> {
+    Token::with_span(token.token_type, token.literal.to_string(), token.span)
+}
+
+fn static_expressions(expressions: &[Expression]) -> Vec<Expression<'static>> {
+    expressions.iter().map(Expression::to_static).collect()
+}
+
+fn static_box(expression: &Expression) -> Box<Expression<'static>> {
+    Box::new(expression.to_static())
+}
+
+// The `to_static` methods copy a node and everything under it out of the source text, for
+// values such as functions that outlive the program they were parsed from.
+
+impl Statement<'_> {
+    pub fn to_static(&self) -> Statement<'static> {
+        match self {
+            Statement::Let(node) => Statement::Let(node.to_static()),
+            Statement::Const(node) => Statement::Const(node.to_static()),
+            Statement::Return(node) => Statement::Return(node.to_static()),
+            Statement::Expression(node) => Statement::Expression(node.to_static()),
+            Statement::While(node) => Statement::While(node.to_static()),
+            Statement::For(node) => Statement::For(node.to_static()),
+            Statement::Break(node) => Statement::Break(BreakStatement {
+                token: static_token(&node.token),
+            }),
+            Statement::Continue(node) => Statement::Continue(ContinueStatement {
+                token: static_token(&node.token),
+            }),
+        }
+    }
+}
+
+impl Expression<'_> {
+    pub fn to_static(&self) -> Expression<'static> {
+        match self {
+            Expression::Identifier(node) => Expression::Identifier(node.to_static()),
+            Expression::IntegerLiteral(node) => Expression::IntegerLiteral(IntegerLiteral {
+                token: static_token(&node.token),
+                value: node.value,
+            }),
+            Expression::BigIntegerLiteral(node) => {
+                Expression::BigIntegerLiteral(BigIntegerLiteral {
+                    token: static_token(&node.token),
+                    value: node.value.clone(),
+                })
+            }
+            Expression::FloatLiteral(node) => Expression::FloatLiteral(FloatLiteral {
+                token: static_token(&node.token),
+                value: node.value,
+            }),
+            Expression::BooleanLiteral(node) => Expression::BooleanLiteral(BooleanLiteral {
+                token: static_token(&node.token),
+                value: node.value,
+            }),
+            Expression::StringLiteral(node) => Expression::StringLiteral(StringLiteral {
+                token: static_token(&node.token),
+                value: Cow::Owned(node.value.to_string()),
+            }),
+            Expression::Prefix(node) => Expression::Prefix(PrefixExpression {
+                token: static_token(&node.token),
+                operator: node.operator,
+                right: static_box(&node.right),
+            }),
+            Expression::Infix(node) => Expression::Infix(InfixExpression {
+                token: static_token(&node.token),
+                left: static_box(&node.left),
+                operator: node.operator,
+                right: static_box(&node.right),
+            }),
+            Expression::Logical(node) => Expression::Logical(LogicalExpression {
+                token: static_token(&node.token),
+                left: static_box(&node.left),
+                operator: node.operator,
+                right: static_box(&node.right),
+            }),
+            Expression::If(node) => Expression::If(IfExpression {
+                token: static_token(&node.token),
+                condition: static_box(&node.condition),
+                consequence: node.consequence.to_static(),
+                alternative: node.alternative.as_ref().map(BlockStatement::to_static),
+            }),
+            Expression::FunctionLiteral(node) => Expression::FunctionLiteral(FunctionLiteral {
+                token: static_token(&node.token),
+                parameters: node.parameters.iter().map(Identifier::to_static).collect(),
+                body: node.body.to_static(),
+                shared: OnceCell::new(),
+            }),
+            Expression::Call(node) => Expression::Call(CallExpression {
+                token: static_token(&node.token),
+                function: static_box(&node.function),
+                arguments: static_expressions(&node.arguments),
+                closing: node.closing,
+            }),
+            Expression::ArrayLiteral(node) => Expression::ArrayLiteral(ArrayLiteral {
+                token: static_token(&node.token),
+                elements: static_expressions(&node.elements),
+                closing: node.closing,
+            }),
+            Expression::Index(node) => Expression::Index(IndexExpression {
+                token: static_token(&node.token),
+                left: static_box(&node.left),
+                index: static_box(&node.index),
+                closing: node.closing,
+            }),
+            Expression::HashLiteral(node) => Expression::HashLiteral(HashLiteral {
+                token: static_token(&node.token),
+                pairs: node.pairs.iter()
+                    .map(|(key, value)| (key.to_static(), value.to_static()))
+                    .collect(),
+                closing: node.closing,
+            }),
+            Expression::Assign(node) => Expression::Assign(AssignExpression {
+                token: static_token(&node.token),
+                target: static_box(&node.target),
+                operator: node.operator,
+                value: static_box(&node.value),
+            }),
+        }
+    }
+}
+
+impl Identifier<'_> {
+    pub fn to_static(&self) -> Identifier<'static> {
+        Identifier {
+            token: static_token(&self.token),
+            value: Cow::Owned(self.value.to_string()),
+        }
+    }
+}
+
+impl LetStatement<'_> {
+    pub fn to_static(&self) -> LetStatement<'static> {
+        LetStatement {
+            token: static_token(&self.token),
+            name: self.name.to_static(),
+            value: self.value.to_static(),
+            doc: self.doc.clone(),
+        }
+    }
+}
+
+impl ConstStatement<'_> {
+    pub fn to_static(&self) -> ConstStatement<'static> {
+        ConstStatement {
+            token: static_token(&self.token),
+            name: self.name.to_static(),
+            value: self.value.to_static(),
+            doc: self.doc.clone(),
+        }
+    }
+}
+
+impl ReturnStatement<'_> {
+    pub fn to_static(&self) -> ReturnStatement<'static> {
+        ReturnStatement {
+            token: static_token(&self.token),
+            value: self.value.as_ref().map(Expression::to_static),
+        }
+    }
+}
+
+impl ExpressionStatement<'_> {
+    pub fn to_static(&self) -> ExpressionStatement<'static> {
+        ExpressionStatement {
+            token: static_token(&self.token),
+            expression: self.expression.as_ref().map(Expression::to_static),
+        }
+    }
+}
+
+impl WhileStatement<'_> {
+    pub fn to_static(&self) -> WhileStatement<'static> {
+        WhileStatement {
+            token: static_token(&self.token),
+            condition: self.condition.to_static(),
+            body: self.body.to_static(),
+        }
+    }
+}
+
+impl ForStatement<'_> {
+    pub fn to_static(&self) -> ForStatement<'static> {
+        ForStatement {
+            token: static_token(&self.token),
+            variable: self.variable.to_static(),
+            iterable: self.iterable.to_static(),
+            body: self.body.to_static(),
+        }
+    }
+}
+
+impl BlockStatement<'_> {
+    pub fn to_static(&self) -> BlockStatement<'static> {
+        BlockStatement {
+            token: static_token(&self.token),
+            statements: self.statements.iter().map(Statement::to_static).collect(),
+            closing: self.closing,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ast::{Identifier, LetStatement, Program};
+    use crate::ast::{Expression, Identifier, LetStatement, Program, Statement};
     use crate::token::{Token, TokenType};
 
     #[test]
@@ -247,18 +893,19 @@ mod tests {
         let token = Token::new(TokenType::Let, "let".to_string());
         let name = Identifier {
             token: Token::new(TokenType::Ident, "myVar".to_string()),
-            value: "myVar".to_string(),
+            value: "myVar".into(),
         };
-        let value = Box::new(Identifier {
+        let value = Expression::Identifier(Identifier {
             token: Token::new(TokenType::Ident, "anotherVar".to_string()),
-            value: "anotherVar".to_string(),
+            value: "anotherVar".into(),
         });
 
         program.statements.push(
-            Box::new(LetStatement {
+            Statement::Let(LetStatement {
                 token,
                 name,
                 value,
+                doc: None,
             })
         );
 
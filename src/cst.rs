@@ -0,0 +1,239 @@
+use std::fmt::{Display, Formatter, Result};
+use crate::error::LexError;
+use crate::lexer::Lexer;
+use crate::token::{Span, TokenType, Trivia};
+
+/// What a `SyntaxNode` groups together. The tree only nests on delimiters; everything inside a
+/// pair of brackets is a flat run of tokens and nested groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Root,
+    Parenthesized,
+    Braced,
+    Bracketed,
+}
+
+/// A token together with its exact source text and surrounding trivia.
+#[derive(Debug, Clone)]
+pub struct SyntaxToken<'src> {
+    pub token_type: TokenType,
+    pub text: &'src str,
+    pub span: Span,
+    pub leading_trivia: Vec<Trivia<'src>>,
+    pub trailing_trivia: Vec<Trivia<'src>>,
+}
+
+impl Display for SyntaxToken<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for trivia in &self.leading_trivia {
+            write!(f, "{}", trivia.text)?;
+        }
+        write!(f, "{}", self.text)?;
+        for trivia in &self.trailing_trivia {
+            write!(f, "{}", trivia.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement<'src> {
+    Node(SyntaxNode<'src>),
+    Token(SyntaxToken<'src>),
+}
+
+impl Display for SyntaxElement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            SyntaxElement::Node(node) => write!(f, "{}", node),
+            SyntaxElement::Token(token) => write!(f, "{}", token),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxNode<'src> {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement<'src>>,
+}
+
+impl<'src> SyntaxNode<'src> {
+    fn new(kind: SyntaxKind) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns every token under this node in source order.
+    pub fn tokens(&self) -> Vec<&SyntaxToken<'src>> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+}
+
+impl Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for child in &self.children {
+            write!(f, "{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+/// A lossless view of a source file for formatters and refactoring tools. Writing the tree back
+/// out with `Display` reproduces the input byte for byte, including malformed input. Tokens and
+/// trivia borrow their text from the source.
+#[derive(Debug, Clone)]
+pub struct SyntaxTree<'src> {
+    pub root: SyntaxNode<'src>,
+    pub errors: Vec<LexError>,
+}
+
+impl<'src> SyntaxTree<'src> {
+    pub fn parse(source: &'src str) -> Self {
+        let mut lexer = Lexer::new(source).with_trivia();
+        let mut stack = vec![SyntaxNode::new(SyntaxKind::Root)];
+
+        loop {
+            let token = lexer.next_token();
+            let token_type = token.token_type;
+            let syntax_token = SyntaxToken {
+                token_type,
+                text: &source[token.span.start.offset..token.span.end.offset],
+                span: token.span,
+                leading_trivia: token.leading_trivia,
+                trailing_trivia: token.trailing_trivia,
+            };
+
+            match token_type {
+                TokenType::LParen | TokenType::LBrace | TokenType::LBracket => {
+                    let kind = match token_type {
+                        TokenType::LParen => SyntaxKind::Parenthesized,
+                        TokenType::LBrace => SyntaxKind::Braced,
+                        _ => SyntaxKind::Bracketed,
+                    };
+                    let mut node = SyntaxNode::new(kind);
+                    node.children.push(SyntaxElement::Token(syntax_token));
+                    stack.push(node);
+                }
+                TokenType::RParen | TokenType::RBrace | TokenType::RBracket
+                    if Self::closes(&stack, token_type) => {
+                    let mut node = stack.pop().unwrap();
+                    node.children.push(SyntaxElement::Token(syntax_token));
+                    stack.last_mut().unwrap().children.push(SyntaxElement::Node(node));
+                }
+                TokenType::EOF => {
+                    while stack.len() > 1 {
+                        let node = stack.pop().unwrap();
+                        stack.last_mut().unwrap().children.push(SyntaxElement::Node(node));
+                    }
+                    stack[0].children.push(SyntaxElement::Token(syntax_token));
+                    break;
+                }
+                _ => stack.last_mut().unwrap().children.push(SyntaxElement::Token(syntax_token)),
+            }
+        }
+
+        Self {
+            root: stack.pop().unwrap(),
+            errors: lexer.take_errors(),
+        }
+    }
+
+    fn closes(stack: &[SyntaxNode], token_type: TokenType) -> bool {
+        match stack.last().map(|node| node.kind) {
+            Some(SyntaxKind::Parenthesized) => token_type == TokenType::RParen,
+            Some(SyntaxKind::Braced) => token_type == TokenType::RBrace,
+            Some(SyntaxKind::Bracketed) => token_type == TokenType::RBracket,
+            _ => false,
+        }
+    }
+}
+
+impl Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cst::{SyntaxElement, SyntaxKind, SyntaxTree};
+    use crate::token::{TokenType, TriviaKind};
+
+    macro_rules! test_round_trip {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let input = $value;
+                assert_eq!(SyntaxTree::parse(input).to_string(), input);
+            }
+        )*
+        }
+    }
+
+    test_round_trip! {
+        test_round_trip_1: ""
+        test_round_trip_2: "let x = 5;"
+        test_round_trip_3: "  let   x=5 ;  \n\n"
+        test_round_trip_4: "// header\r\nlet add = fn(x, y) {\r\n    x + y; // sum\r\n};\r\n"
+        test_round_trip_5: "/* a /* b */ */ if (a) { b } else { c }\n/// docs\nlet s = \"\\\"\\n\";"
+        test_round_trip_6: "let größe = \"日本\";\t// ünïcödé\n"
+        test_round_trip_7: "let broken = ) (( @ \"unterminated"
+        test_round_trip_8: "x /* unterminated"
+        test_round_trip_9: "let a = [1, [2 ]] ;\na[0] ]["
+        test_round_trip_10: "let a = 1;\0 let b = 2;"
+    }
+
+    #[test]
+    fn test_trivia_attachment() {
+        let tree = SyntaxTree::parse("let a = 1; // one\n  a");
+        let tokens = tree.root.tokens();
+
+        let semicolon = tokens[4];
+        assert_eq!(semicolon.token_type, TokenType::Semicolon);
+        let trailing: Vec<_> = semicolon.trailing_trivia.iter()
+            .map(|trivia| (trivia.kind, trivia.text.as_ref()))
+            .collect();
+        assert_eq!(trailing, vec![
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::LineComment, "// one"),
+        ]);
+
+        let identifier = tokens[5];
+        assert_eq!(identifier.text, "a");
+        let leading: Vec<_> = identifier.leading_trivia.iter()
+            .map(|trivia| (trivia.kind, trivia.text.as_ref()))
+            .collect();
+        assert_eq!(leading, vec![(TriviaKind::Newline, "\n"), (TriviaKind::Whitespace, "  ")]);
+    }
+
+    #[test]
+    fn test_tree_shape() {
+        let tree = SyntaxTree::parse("f(a, (b)) { c } [d]");
+        let kinds: Vec<_> = tree.root.children.iter()
+            .map(|child| match child {
+                SyntaxElement::Node(node) => format!("{:?}", node.kind),
+                SyntaxElement::Token(token) => token.text.to_string(),
+            })
+            .collect();
+        assert_eq!(kinds, vec!["f", "Parenthesized", "Braced", "Bracketed", ""]);
+
+        let SyntaxElement::Node(call) = &tree.root.children[1] else {
+            panic!("expected a node");
+        };
+        assert_eq!(call.kind, SyntaxKind::Parenthesized);
+        // Trailing trivia belongs to the closing parenthesis, so it stays with the group.
+        assert_eq!(call.to_string(), "(a, (b)) ");
+        assert!(matches!(&call.children[3], SyntaxElement::Node(node)
+            if node.kind == SyntaxKind::Parenthesized));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::token::Span;
+
+/// An error that can be reported against a location in the source text.
+pub trait Diagnostic {
+    fn message(&self) -> String;
+    fn span(&self) -> Span;
+    /// A short note printed next to the underline, if there is anything useful to add.
+    fn hint(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Renders `diagnostic` in the style of rustc: the message, the location, the offending source
+/// line and a caret underline beneath the span.
+///
+/// ```text
+/// error: expected `=`, found `INT`
+///  --> 1:7
+///   |
+/// 1 | let x 5;
+///   |       ^ expected `=` here
+/// ```
+pub fn render(source: &str, diagnostic: &dyn Diagnostic) -> String {
+    let span = diagnostic.span();
+    let line_number = span.start.line.max(1);
+    let line = source.lines().nth(line_number - 1).unwrap_or("");
+    let line_start = line_start_offset(source, span.start.offset);
+
+    let column = line[..(span.start.offset - line_start).min(line.len())].chars().count();
+    let underline_end = (span.end.offset.max(span.start.offset) - line_start).min(line.len());
+    let width = line[column_byte(line, column)..underline_end].chars().count().max(1);
+
+    let gutter = " ".repeat(line_number.to_string().len());
+    let mut out = String::new();
+    out.push_str(&format!("error: {}\n", diagnostic.message()));
+    out.push_str(&format!("{}--> {}:{}\n", gutter, line_number, column + 1));
+    out.push_str(&format!("{} |\n", gutter));
+    out.push_str(&format!("{} | {}\n", line_number, line));
+    out.push_str(&format!("{} | {}{}", gutter, " ".repeat(column), "^".repeat(width)));
+    if let Some(hint) = diagnostic.hint() {
+        out.push_str(&format!(" {}", hint));
+    }
+    out.push('\n');
+    out
+}
+
+fn line_start_offset(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    match source[..offset].rfind('\n') {
+        Some(index) => index + 1,
+        None => 0,
+    }
+}
+
+fn column_byte(line: &str, column: usize) -> usize {
+    line.char_indices().nth(column).map(|(index, _)| index).unwrap_or(line.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{render, Diagnostic};
+    use crate::token::{Position, Span};
+
+    struct TestDiagnostic {
+        span: Span,
+    }
+
+    impl Diagnostic for TestDiagnostic {
+        fn message(&self) -> String {
+            String::from("something went wrong")
+        }
+
+        fn span(&self) -> Span {
+            self.span
+        }
+
+        fn hint(&self) -> Option<String> {
+            Some(String::from("here"))
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "let a = 1;\nlet bb = a +* 2;\n";
+        let diagnostic = TestDiagnostic {
+            span: Span::new(Position::new(22, 2, 12), Position::new(23, 2, 13)),
+        };
+
+        assert_eq!(render(source, &diagnostic), "error: something went wrong
+ --> 2:12
+  |
+2 | let bb = a +* 2;
+  |            ^ here
+");
+    }
+
+    #[test]
+    fn test_render_multi_character_span() {
+        let source = "let total = 99999999999999999999;";
+        let diagnostic = TestDiagnostic {
+            span: Span::new(Position::new(12, 1, 13), Position::new(32, 1, 33)),
+        };
+
+        assert_eq!(render(source, &diagnostic), "error: something went wrong
+ --> 1:13
+  |
+1 | let total = 99999999999999999999;
+  |             ^^^^^^^^^^^^^^^^^^^^ here
+");
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let source = "let x = (1 + 2";
+        let diagnostic = TestDiagnostic {
+            span: Span::new(Position::new(14, 1, 15), Position::new(14, 1, 15)),
+        };
+
+        assert_eq!(render(source, &diagnostic), "error: something went wrong
+ --> 1:15
+  |
+1 | let x = (1 + 2
+  |               ^ here
+");
+    }
+
+    #[test]
+    fn test_render_multibyte_line() {
+        let source = "let größe = 5 @ 2;";
+        let diagnostic = TestDiagnostic {
+            span: Span::new(Position::new(16, 1, 15), Position::new(17, 1, 16)),
+        };
+
+        assert_eq!(render(source, &diagnostic), "error: something went wrong
+ --> 1:15
+  |
+1 | let größe = 5 @ 2;
+  |               ^ here
+");
+    }
+}
@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::object::Object;
+
+/// The reason `Environment::assign` refused to update a binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignError {
+    Undefined,
+    Const,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    store: HashMap<String, Object>,
+    /// The names in `store` that were bound with `const`.
+    consts: HashSet<String>,
+    outer: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self {
+            store: HashMap::new(),
+            consts: HashSet::new(),
+            outer: None,
+        }
+    }
+
+    /// Creates an environment whose lookups fall back to `outer`, as used for function calls.
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            store: HashMap::new(),
+            consts: HashSet::new(),
+            outer: Some(outer),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.outer.as_ref().and_then(|outer| outer.borrow().get(name)),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: Object) -> Object {
+        self.consts.remove(name);
+        self.store.insert(name.to_string(), value.clone());
+        value
+    }
+
+    /// Binds `name` like `set`, but `assign` will refuse to change it afterwards.
+    pub fn set_const(&mut self, name: &str, value: Object) -> Object {
+        self.consts.insert(name.to_string());
+        self.store.insert(name.to_string(), value.clone());
+        value
+    }
+
+    /// Returns true if `name` is bound with `const` in this environment, ignoring `outer`.
+    pub fn is_const(&self, name: &str) -> bool {
+        self.consts.contains(name)
+    }
+
+    /// Updates an existing binding in the innermost environment that has one. Every environment
+    /// is left unchanged if `name` is not bound anywhere or its binding is a const.
+    pub fn assign(&mut self, name: &str, value: Object) -> Result<Object, AssignError> {
+        if self.consts.contains(name) {
+            return Err(AssignError::Const);
+        }
+        match self.store.get_mut(name) {
+            Some(slot) => {
+                *slot = value.clone();
+                Ok(value)
+            }
+            None => match &self.outer {
+                Some(outer) => outer.borrow_mut().assign(name, value),
+                None => Err(AssignError::Undefined),
+            },
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt::{Display, Formatter, Result};
+use crate::diagnostic::Diagnostic;
+use crate::token::{integer_radix, Span, TokenType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    IllegalCharacter {
+        ch: char,
+        span: Span,
+    },
+    UnterminatedString {
+        span: Span,
+    },
+    UnterminatedComment {
+        span: Span,
+    },
+    InvalidEscape {
+        sequence: String,
+        span: Span,
+    },
+    MalformedNumber {
+        literal: String,
+        span: Span,
+    },
+    InvalidDigit {
+        digit: char,
+        radix: u32,
+        span: Span,
+    },
+    /// Reading a streamed input failed; lexing stops where the error happened.
+    Io {
+        message: String,
+        span: Span,
+    },
+}
+
+impl Diagnostic for LexError {
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            LexError::IllegalCharacter { span, .. } => *span,
+            LexError::UnterminatedString { span } => *span,
+            LexError::UnterminatedComment { span } => *span,
+            LexError::InvalidEscape { span, .. } => *span,
+            LexError::MalformedNumber { span, .. } => *span,
+            LexError::InvalidDigit { span, .. } => *span,
+            LexError::Io { span, .. } => *span,
+        }
+    }
+
+    fn hint(&self) -> Option<String> {
+        match self {
+            LexError::IllegalCharacter { .. } => None,
+            LexError::UnterminatedString { .. } => Some(String::from("missing closing `\"`")),
+            LexError::UnterminatedComment { .. } => Some(String::from("missing closing `*/`")),
+            LexError::InvalidEscape { .. } => {
+                Some(String::from("expected one of `\\n`, `\\t`, `\\\\`, `\\\"` or `\\u{...}`"))
+            }
+            LexError::MalformedNumber { literal, .. } if integer_radix(literal).0 != 10 => {
+                Some(String::from("expected digits after the prefix"))
+            }
+            LexError::MalformedNumber { .. } => {
+                Some(String::from("a number has at most one `.` and digits after any exponent"))
+            }
+            LexError::InvalidDigit { radix, .. } => {
+                let digits = match radix {
+                    2 => "`0` and `1`",
+                    8 => "`0` to `7`",
+                    _ => "`0` to `9` and `a` to `f`",
+                };
+                Some(format!("{} literals only use {}", radix_name(*radix), digits))
+            }
+            LexError::Io { .. } => None,
+        }
+    }
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            LexError::IllegalCharacter { ch, .. } => write!(f, "illegal character `{}`", ch),
+            LexError::UnterminatedString { .. } => write!(f, "unterminated string literal"),
+            LexError::UnterminatedComment { .. } => write!(f, "unterminated block comment"),
+            LexError::InvalidEscape { sequence, .. } => {
+                write!(f, "invalid escape sequence `{}`", sequence)
+            }
+            LexError::MalformedNumber { literal, .. } => {
+                write!(f, "malformed number literal `{}`", literal)
+            }
+            LexError::InvalidDigit { digit, radix, .. } => {
+                write!(f, "invalid digit `{}` in {} literal", digit, radix_name(*radix))
+            }
+            LexError::Io { message, .. } => write!(f, "failed to read input: {}", message),
+        }
+    }
+}
+
+fn radix_name(radix: u32) -> &'static str {
+    match radix {
+        2 => "binary",
+        8 => "octal",
+        16 => "hexadecimal",
+        _ => "decimal",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    Lex(LexError),
+    UnexpectedToken {
+        expected: TokenType,
+        found: TokenType,
+        span: Span,
+    },
+    NoPrefixParseFunction {
+        found: TokenType,
+        span: Span,
+    },
+    /// The input ended while a construct was still open.
+    Unterminated {
+        expected: TokenType,
+        found: TokenType,
+        span: Span,
+    },
+    /// The left-hand side of `=` or a compound assignment is not a name or an index into one.
+    InvalidAssignmentTarget {
+        target: String,
+        span: Span,
+    },
+    /// A `break` or `continue` that is not inside a loop body of the same function.
+    OutsideLoop {
+        keyword: String,
+        span: Span,
+    },
+}
+
+impl Diagnostic for ParseError {
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            ParseError::Lex(error) => error.span(),
+            ParseError::UnexpectedToken { span, .. } => *span,
+            ParseError::NoPrefixParseFunction { span, .. } => *span,
+            ParseError::Unterminated { span, .. } => *span,
+            ParseError::InvalidAssignmentTarget { span, .. } => *span,
+            ParseError::OutsideLoop { span, .. } => *span,
+        }
+    }
+
+    fn hint(&self) -> Option<String> {
+        match self {
+            ParseError::Lex(error) => error.hint(),
+            ParseError::UnexpectedToken { expected, .. } => {
+                Some(format!("expected `{}` here", expected.as_str()))
+            }
+            ParseError::NoPrefixParseFunction { .. } => {
+                Some(String::from("expected an expression here"))
+            }
+            ParseError::Unterminated { expected, .. } => {
+                Some(format!("input ends here, add `{}`", expected.as_str()))
+            }
+            ParseError::InvalidAssignmentTarget { .. } => {
+                Some(String::from("only a name or an index into one can be assigned to"))
+            }
+            ParseError::OutsideLoop { .. } => {
+                Some(String::from("only allowed inside a `while` or `for` body"))
+            }
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ParseError::Lex(error) => write!(f, "{}", error),
+            ParseError::UnexpectedToken { expected, found, .. } => {
+                write!(f, "expected `{}`, found `{}`", expected.as_str(), found.as_str())
+            }
+            ParseError::NoPrefixParseFunction { found, .. } => {
+                write!(f, "no prefix parse function for `{}` found", found.as_str())
+            }
+            ParseError::Unterminated { expected, found, .. } => {
+                write!(f, "unterminated input: expected `{}`, found `{}`",
+                       expected.as_str(), found.as_str())
+            }
+            ParseError::InvalidAssignmentTarget { target, .. } => {
+                write!(f, "invalid assignment target `{}`", target)
+            }
+            ParseError::OutsideLoop { keyword, .. } => {
+                write!(f, "`{}` outside of a loop", keyword)
+            }
+        }
+    }
+}
+
+/// An error found by `resolver::Resolver` in a program that parsed cleanly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    /// A const binding is assigned to, or declared again in the same scope. `declared` is the
+    /// name in the `const` statement.
+    ConstReassignment {
+        name: String,
+        span: Span,
+        declared: Span,
+    },
+}
+
+impl Diagnostic for ResolveError {
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            ResolveError::ConstReassignment { span, .. } => *span,
+        }
+    }
+
+    fn hint(&self) -> Option<String> {
+        match self {
+            ResolveError::ConstReassignment { declared, .. } => {
+                Some(format!("declared const at {}:{}", declared.start.line, declared.start.column))
+            }
+        }
+    }
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ResolveError::ConstReassignment { name, .. } => {
+                write!(f, "cannot reassign const `{}`", name)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::render;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_render_parse_error() {
+        let input = "let x = 1;\nlet y 2;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 1);
+        assert_eq!(render(input, &parser.errors()[0]), "error: expected `=`, found `INT`
+ --> 2:7
+  |
+2 | let y 2;
+  |       ^ expected `=` here
+");
+    }
+
+    #[test]
+    fn test_render_resolve_error() {
+        let input = "const max = 3;\nmax += 1;";
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        let errors = Resolver::new().resolve(&program);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(render(input, &errors[0]), "error: cannot reassign const `max`
+ --> 2:1
+  |
+2 | max += 1;
+  | ^^^ declared const at 1:7
+");
+    }
+}
@@ -0,0 +1,1244 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use crate::ast::{AssignExpression, BlockStatement, CallExpression, Expression, ForStatement,
+                 HashLiteral, Identifier, IfExpression, LogicalExpression, Program,
+                 SharedFunction, Statement, WhileStatement};
+use crate::environment::{AssignError, Environment};
+use crate::object::{Function, Hash, Object, FALSE, NULL, TRUE};
+
+fn native_bool_to_boolean_object(value: bool) -> Object {
+    if value { TRUE } else { FALSE }
+}
+
+fn new_error(message: String) -> Object {
+    Object::Error(message)
+}
+
+pub fn eval_program(program: &Program, environment: &Rc<RefCell<Environment>>) -> Object {
+    let mut result = NULL;
+    for statement in &program.statements {
+        result = eval_statement(statement, environment);
+        match result {
+            Object::ReturnValue(value) => return *value,
+            Object::Error(_) => return result,
+            _ => {}
+        }
+    }
+    result
+}
+
+fn eval_block_statement(block: &BlockStatement, environment: &Rc<RefCell<Environment>>) -> Object {
+    let mut result = NULL;
+    for statement in &block.statements {
+        result = eval_statement(statement, environment);
+        if result.is_unwinding() {
+            return result;
+        }
+    }
+    result
+}
+
+fn eval_statement(statement: &Statement, environment: &Rc<RefCell<Environment>>) -> Object {
+    match statement {
+        Statement::Let(let_statement) => {
+            eval_binding(&let_statement.name, &let_statement.value, false, environment)
+        }
+        Statement::Const(const_statement) => {
+            eval_binding(&const_statement.name, &const_statement.value, true, environment)
+        }
+        Statement::Return(return_statement) => {
+            let value = match &return_statement.value {
+                Some(value) => eval_expression(value, environment),
+                None => NULL,
+            };
+            if value.is_unwinding() {
+                return value;
+            }
+            Object::ReturnValue(Box::new(value))
+        }
+        Statement::Expression(expression_statement) => {
+            match &expression_statement.expression {
+                Some(expression) => eval_expression(expression, environment),
+                None => NULL,
+            }
+        }
+        Statement::While(while_statement) => eval_while_statement(while_statement, environment),
+        Statement::For(for_statement) => eval_for_statement(for_statement, environment),
+        Statement::Break(_) => Object::Break,
+        Statement::Continue(_) => Object::Continue,
+    }
+}
+
+/// The resolver reports rebinding a const before the program runs, but `let` is checked here too
+/// so that programs evaluated without the resolver cannot change a const either. A `const`
+/// statement may bind its name again, since one in a loop body runs on every pass.
+fn eval_binding(name: &Identifier, value: &Expression, constant: bool,
+                environment: &Rc<RefCell<Environment>>) -> Object {
+    let value = eval_expression(value, environment);
+    if value.is_unwinding() {
+        return value;
+    }
+    let mut environment = environment.borrow_mut();
+    if constant {
+        environment.set_const(&name.value, value);
+    } else if environment.is_const(&name.value) {
+        return new_error(format!("cannot assign to const: {}", name.value));
+    } else {
+        environment.set(&name.value, value);
+    }
+    NULL
+}
+
+fn eval_while_statement(while_statement: &WhileStatement,
+                        environment: &Rc<RefCell<Environment>>) -> Object {
+    loop {
+        let condition = eval_expression(&while_statement.condition, environment);
+        if condition.is_unwinding() {
+            return condition;
+        }
+        if !condition.is_truthy() {
+            return NULL;
+        }
+        match eval_block_statement(&while_statement.body, environment) {
+            Object::Break => return NULL,
+            result @ (Object::ReturnValue(_) | Object::Error(_)) => return result,
+            _ => {}
+        }
+    }
+}
+
+/// Arrays yield their elements, strings their characters and hashes their keys in insertion
+/// order. The collection is evaluated once, before the first iteration.
+fn eval_for_statement(for_statement: &ForStatement,
+                      environment: &Rc<RefCell<Environment>>) -> Object {
+    let iterable = eval_expression(&for_statement.iterable, environment);
+    if iterable.is_unwinding() {
+        return iterable;
+    }
+    let items = match iterable {
+        Object::Array(elements) => elements,
+        Object::String(value) => value.chars().map(|ch| Object::String(ch.to_string())).collect(),
+        Object::Hash(hash) => hash.pairs().iter().map(|(key, _)| key.clone()).collect(),
+        _ => return new_error(format!("cannot iterate over {}", iterable.object_type())),
+    };
+
+    if environment.borrow().is_const(&for_statement.variable.value) {
+        return new_error(format!("cannot assign to const: {}", for_statement.variable.value));
+    }
+    for item in items {
+        environment.borrow_mut().set(&for_statement.variable.value, item);
+        match eval_block_statement(&for_statement.body, environment) {
+            Object::Break => break,
+            result @ (Object::ReturnValue(_) | Object::Error(_)) => return result,
+            _ => {}
+        }
+    }
+    NULL
+}
+
+fn eval_expression(expression: &Expression, environment: &Rc<RefCell<Environment>>) -> Object {
+    match expression {
+        Expression::IntegerLiteral(integer_literal) => Object::Integer(integer_literal.value),
+        Expression::BigIntegerLiteral(big_integer_literal) => {
+            Object::from(big_integer_literal.value.clone())
+        }
+        Expression::FloatLiteral(float_literal) => Object::Float(float_literal.value),
+        Expression::StringLiteral(string_literal) => {
+            Object::String(string_literal.value.to_string())
+        }
+        Expression::BooleanLiteral(boolean_literal) => {
+            native_bool_to_boolean_object(boolean_literal.value)
+        }
+        Expression::Identifier(identifier) => eval_identifier(identifier, environment),
+        Expression::Prefix(prefix_expression) => {
+            let right = eval_expression(&prefix_expression.right, environment);
+            if right.is_unwinding() {
+                return right;
+            }
+            eval_prefix_expression(prefix_expression.operator, right)
+        }
+        Expression::Infix(infix_expression) => {
+            let left = eval_expression(&infix_expression.left, environment);
+            if left.is_unwinding() {
+                return left;
+            }
+            let right = eval_expression(&infix_expression.right, environment);
+            if right.is_unwinding() {
+                return right;
+            }
+            eval_infix_expression(infix_expression.operator, left, right)
+        }
+        Expression::Logical(logical_expression) => {
+            eval_logical_expression(logical_expression, environment)
+        }
+        Expression::If(if_expression) => eval_if_expression(if_expression, environment),
+        Expression::FunctionLiteral(function_literal) => {
+            let SharedFunction { parameters, body } = function_literal.shared();
+            Object::Function(Rc::new(Function {
+                parameters,
+                body,
+                environment: Rc::clone(environment),
+            }))
+        }
+        Expression::Call(call_expression) => eval_call_expression(call_expression, environment),
+        Expression::ArrayLiteral(array_literal) => {
+            match eval_expressions(&array_literal.elements, environment) {
+                Ok(elements) => Object::Array(elements),
+                Err(error) => error,
+            }
+        }
+        Expression::Index(index_expression) => {
+            let left = eval_expression(&index_expression.left, environment);
+            if left.is_unwinding() {
+                return left;
+            }
+            let index = eval_expression(&index_expression.index, environment);
+            if index.is_unwinding() {
+                return index;
+            }
+            eval_index_expression(left, index)
+        }
+        Expression::HashLiteral(hash_literal) => eval_hash_literal(hash_literal, environment),
+        Expression::Assign(assign_expression) => {
+            eval_assign_expression(assign_expression, environment)
+        }
+    }
+}
+
+fn eval_expressions(expressions: &[Expression], environment: &Rc<RefCell<Environment>>)
+                    -> Result<Vec<Object>, Object> {
+    let mut result = Vec::new();
+    for expression in expressions {
+        let evaluated = eval_expression(expression, environment);
+        if evaluated.is_unwinding() {
+            return Err(evaluated);
+        }
+        result.push(evaluated);
+    }
+    Ok(result)
+}
+
+fn eval_call_expression(call_expression: &CallExpression,
+                        environment: &Rc<RefCell<Environment>>) -> Object {
+    let function = eval_expression(&call_expression.function, environment);
+    if function.is_unwinding() {
+        return function;
+    }
+    let arguments = match eval_expressions(&call_expression.arguments, environment) {
+        Ok(arguments) => arguments,
+        Err(error) => return error,
+    };
+    apply_function(function, arguments)
+}
+
+fn apply_function(function: Object, arguments: Vec<Object>) -> Object {
+    let Object::Function(function) = function else {
+        return new_error(format!("not a function: {}", function.object_type()));
+    };
+    if function.parameters.len() != arguments.len() {
+        return new_error(format!("wrong number of arguments: expected {}, got {}",
+                                 function.parameters.len(), arguments.len()));
+    }
+
+    let mut extended = Environment::new_enclosed(Rc::clone(&function.environment));
+    for (parameter, argument) in function.parameters.iter().zip(arguments) {
+        extended.set(&parameter.value, argument);
+    }
+    match eval_block_statement(&function.body, &Rc::new(RefCell::new(extended))) {
+        Object::ReturnValue(value) => *value,
+        evaluated => evaluated,
+    }
+}
+
+fn eval_if_expression(if_expression: &IfExpression,
+                      environment: &Rc<RefCell<Environment>>) -> Object {
+    let condition = eval_expression(&if_expression.condition, environment);
+    if condition.is_unwinding() {
+        return condition;
+    }
+
+    if condition.is_truthy() {
+        eval_block_statement(&if_expression.consequence, environment)
+    } else if let Some(alternative) = &if_expression.alternative {
+        eval_block_statement(alternative, environment)
+    } else {
+        NULL
+    }
+}
+
+/// `&&` and `||` only evaluate their right operand when the left one does not settle the result.
+fn eval_logical_expression(logical_expression: &LogicalExpression,
+                           environment: &Rc<RefCell<Environment>>) -> Object {
+    let left = eval_expression(&logical_expression.left, environment);
+    if left.is_unwinding() {
+        return left;
+    }
+    match (logical_expression.operator, left.is_truthy()) {
+        ("&&", false) => return FALSE,
+        ("||", true) => return TRUE,
+        _ => {}
+    }
+    let right = eval_expression(&logical_expression.right, environment);
+    if right.is_unwinding() {
+        return right;
+    }
+    native_bool_to_boolean_object(right.is_truthy())
+}
+
+fn eval_hash_literal(hash_literal: &HashLiteral, environment: &Rc<RefCell<Environment>>) -> Object {
+    let mut hash = Hash::new();
+    for (key_expression, value_expression) in &hash_literal.pairs {
+        let key = eval_expression(key_expression, environment);
+        if key.is_unwinding() {
+            return key;
+        }
+        let Some(hash_key) = key.hash_key() else {
+            return new_error(format!("unusable as hash key: {}", key.object_type()));
+        };
+        let value = eval_expression(value_expression, environment);
+        if value.is_unwinding() {
+            return value;
+        }
+        hash.insert(hash_key, key, value);
+    }
+    Object::Hash(hash)
+}
+
+/// Indexing past either end of an array gives `null` rather than an error.
+fn eval_index_expression(left: Object, index: Object) -> Object {
+    match (&left, &index) {
+        (Object::Array(elements), Object::Integer(index)) => usize::try_from(*index).ok()
+            .and_then(|index| elements.get(index))
+            .cloned()
+            .unwrap_or(NULL),
+        (Object::Array(_), Object::BigInteger(_)) => NULL,
+        (Object::Hash(hash), _) => match index.hash_key() {
+            Some(key) => hash.get(&key).cloned().unwrap_or(NULL),
+            None => new_error(format!("unusable as hash key: {}", index.object_type())),
+        },
+        _ => new_error(format!("index operator not supported: {}[{}]",
+                               left.object_type(), index.object_type())),
+    }
+}
+
+/// Collections are values, so assigning through an index builds an updated copy of the
+/// collection and binds that to the name at the root of the target. Each index in the target is
+/// evaluated once, before the value, and the root is read after the value in case evaluating it
+/// changed the collection.
+fn eval_assign_expression(assign_expression: &AssignExpression,
+                          environment: &Rc<RefCell<Environment>>) -> Object {
+    let (name, path) = match eval_assignment_path(&assign_expression.target, environment) {
+        Ok(place) => place,
+        Err(error) => return error,
+    };
+
+    // A compound operator such as `+=` applies its infix operator to the current value, which is
+    // read before the right-hand side is evaluated.
+    let operator = assign_expression.operator.strip_suffix('=').filter(|o| !o.is_empty());
+    let current = match operator {
+        Some(_) => {
+            let Some(root) = environment.borrow().get(name) else {
+                return new_error(format!("identifier not found: {}", name));
+            };
+            let current = path.iter().fold(root, |current, index| {
+                if current.is_unwinding() {
+                    return current;
+                }
+                eval_index_expression(current, index.clone())
+            });
+            if current.is_unwinding() {
+                return current;
+            }
+            Some(current)
+        }
+        None => None,
+    };
+
+    let mut value = eval_expression(&assign_expression.value, environment);
+    if value.is_unwinding() {
+        return value;
+    }
+    if let (Some(operator), Some(current)) = (operator, current) {
+        value = eval_infix_expression(operator, current, value);
+        if value.is_unwinding() {
+            return value;
+        }
+    }
+
+    let Some(root) = environment.borrow().get(name) else {
+        return new_error(format!("identifier not found: {}", name));
+    };
+    let updated = assign_at_path(root, &path, value.clone());
+    if updated.is_unwinding() {
+        return updated;
+    }
+    match environment.borrow_mut().assign(name, updated) {
+        Ok(_) => value,
+        Err(AssignError::Undefined) => new_error(format!("identifier not found: {}", name)),
+        Err(AssignError::Const) => new_error(format!("cannot assign to const: {}", name)),
+    }
+}
+
+/// Splits an assignment target into the name it starts from and the evaluated indices that
+/// follow it, so `a[i][j]` gives `a` and the values of `i` and `j`.
+fn eval_assignment_path<'a>(target: &'a Expression, environment: &Rc<RefCell<Environment>>)
+                            -> Result<(&'a str, Vec<Object>), Object> {
+    match target {
+        Expression::Identifier(identifier) => Ok((&identifier.value, Vec::new())),
+        Expression::Index(index_expression) => {
+            let (name, mut path) = eval_assignment_path(&index_expression.left, environment)?;
+            let index = eval_expression(&index_expression.index, environment);
+            if index.is_unwinding() {
+                return Err(index);
+            }
+            path.push(index);
+            Ok((name, path))
+        }
+        _ => Err(new_error(format!("invalid assignment target: {}", target))),
+    }
+}
+
+fn assign_at_path(container: Object, path: &[Object], value: Object) -> Object {
+    let Some((index, rest)) = path.split_first() else {
+        return value;
+    };
+    match (container, index) {
+        (Object::Array(mut elements), Object::Integer(_) | Object::BigInteger(_)) => {
+            let position = match index {
+                Object::Integer(position) => usize::try_from(*position).ok(),
+                _ => None,
+            };
+            let Some(slot) = position.and_then(|position| elements.get_mut(position)) else {
+                return new_error(format!("index out of range: {}", index));
+            };
+            let updated = assign_at_path(std::mem::replace(slot, NULL), rest, value);
+            if updated.is_unwinding() {
+                return updated;
+            }
+            *slot = updated;
+            Object::Array(elements)
+        }
+        (Object::Hash(mut hash), _) => {
+            let Some(key) = index.hash_key() else {
+                return new_error(format!("unusable as hash key: {}", index.object_type()));
+            };
+            let current = hash.get(&key).cloned().unwrap_or(NULL);
+            let updated = assign_at_path(current, rest, value);
+            if updated.is_unwinding() {
+                return updated;
+            }
+            hash.insert(key, index.clone(), updated);
+            Object::Hash(hash)
+        }
+        (container, _) => new_error(format!("index assignment not supported: {}[{}]",
+                                            container.object_type(), index.object_type())),
+    }
+}
+
+fn eval_identifier(identifier: &Identifier, environment: &Rc<RefCell<Environment>>) -> Object {
+    match environment.borrow().get(&identifier.value) {
+        Some(value) => value,
+        None => new_error(format!("identifier not found: {}", identifier.value)),
+    }
+}
+
+fn eval_prefix_expression(operator: &str, right: Object) -> Object {
+    match operator {
+        "!" => native_bool_to_boolean_object(!right.is_truthy()),
+        "-" => eval_minus_prefix_operator_expression(right),
+        "~" => eval_tilde_prefix_operator_expression(right),
+        _ => new_error(format!("unknown operator: {}{}", operator, right.object_type())),
+    }
+}
+
+fn eval_minus_prefix_operator_expression(right: Object) -> Object {
+    match right {
+        Object::Integer(value) => match value.checked_neg() {
+            Some(value) => Object::Integer(value),
+            None => Object::from(-BigInt::from(value)),
+        },
+        Object::BigInteger(value) => Object::from(-value),
+        Object::Float(value) => Object::Float(-value),
+        _ => new_error(format!("unknown operator: -{}", right.object_type())),
+    }
+}
+
+fn eval_tilde_prefix_operator_expression(right: Object) -> Object {
+    match right {
+        Object::Integer(value) => Object::Integer(!value),
+        Object::BigInteger(value) => Object::from(!value),
+        _ => new_error(format!("unknown operator: ~{}", right.object_type())),
+    }
+}
+
+fn eval_infix_expression(operator: &str, left: Object, right: Object) -> Object {
+    match (&left, &right) {
+        (Object::Integer(left), Object::Integer(right)) => {
+            eval_integer_infix_expression(operator, *left, *right)
+        }
+        (Object::BigInteger(left), Object::Integer(right)) => {
+            eval_big_integer_infix_expression(operator, left.clone(), BigInt::from(*right))
+        }
+        (Object::Integer(left), Object::BigInteger(right)) => {
+            eval_big_integer_infix_expression(operator, BigInt::from(*left), right.clone())
+        }
+        (Object::BigInteger(left), Object::BigInteger(right)) => {
+            eval_big_integer_infix_expression(operator, left.clone(), right.clone())
+        }
+        (Object::Float(left), Object::Float(right)) => {
+            eval_float_infix_expression(operator, *left, *right)
+        }
+        // Bitwise operators are undefined on floats, so report the operand types as written
+        // rather than after promotion.
+        (Object::Integer(_) | Object::BigInteger(_), Object::Float(_))
+        | (Object::Float(_), Object::Integer(_) | Object::BigInteger(_))
+            if matches!(operator, "&" | "|" | "^" | "<<" | ">>") => {
+            new_error(format!("unknown operator: {} {} {}",
+                              left.object_type(), operator, right.object_type()))
+        }
+        // Mixed arithmetic promotes the integer to a float.
+        (Object::Integer(left), Object::Float(right)) => {
+            eval_float_infix_expression(operator, *left as f64, *right)
+        }
+        (Object::Float(left), Object::Integer(right)) => {
+            eval_float_infix_expression(operator, *left, *right as f64)
+        }
+        (Object::BigInteger(left), Object::Float(right)) => {
+            eval_float_infix_expression(operator, big_integer_to_f64(left), *right)
+        }
+        (Object::Float(left), Object::BigInteger(right)) => {
+            eval_float_infix_expression(operator, *left, big_integer_to_f64(right))
+        }
+        (Object::String(left), Object::String(right)) => match operator {
+            "+" => Object::String(format!("{}{}", left, right)),
+            "==" => native_bool_to_boolean_object(left == right),
+            "!=" => native_bool_to_boolean_object(left != right),
+            _ => new_error(format!("unknown operator: STRING {} STRING", operator)),
+        },
+        (Object::Boolean(left), Object::Boolean(right)) => match operator {
+            "==" => native_bool_to_boolean_object(left == right),
+            "!=" => native_bool_to_boolean_object(left != right),
+            _ => new_error(format!("unknown operator: BOOLEAN {} BOOLEAN", operator)),
+        },
+        _ if left.object_type() != right.object_type() => {
+            new_error(format!("type mismatch: {} {} {}",
+                              left.object_type(), operator, right.object_type()))
+        }
+        _ => new_error(format!("unknown operator: {} {} {}",
+                               left.object_type(), operator, right.object_type())),
+    }
+}
+
+fn eval_integer_infix_expression(operator: &str, left: i64, right: i64) -> Object {
+    let result = match operator {
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "/" => {
+            if right == 0 {
+                return new_error(format!("division by zero: {} / {}", left, right));
+            }
+            left.checked_div(right)
+        }
+        "%" => {
+            if right == 0 {
+                return new_error(format!("division by zero: {} % {}", left, right));
+            }
+            left.checked_rem(right)
+        }
+        "<" => return native_bool_to_boolean_object(left < right),
+        ">" => return native_bool_to_boolean_object(left > right),
+        "<=" => return native_bool_to_boolean_object(left <= right),
+        ">=" => return native_bool_to_boolean_object(left >= right),
+        "&" => return Object::Integer(left & right),
+        "|" => return Object::Integer(left | right),
+        "^" => return Object::Integer(left ^ right),
+        // Bits shifted out of the top promote the result, so `<<` never silently wraps.
+        "<<" if (0..64).contains(&right) && (left << right) >> right == left => {
+            Some(left << right)
+        }
+        "<<" => None,
+        ">>" if right >= 0 => return Object::Integer(left >> right.min(63)),
+        ">>" => None,
+        "==" => return native_bool_to_boolean_object(left == right),
+        "!=" => return native_bool_to_boolean_object(left != right),
+        _ => return new_error(format!("unknown operator: INTEGER {} INTEGER", operator)),
+    };
+    match result {
+        Some(value) => Object::Integer(value),
+        // The result does not fit in an i64, so it is worked out again with arbitrary precision.
+        None => {
+            eval_big_integer_infix_expression(operator, BigInt::from(left), BigInt::from(right))
+        }
+    }
+}
+
+fn eval_big_integer_infix_expression(operator: &str, left: BigInt, right: BigInt) -> Object {
+    match operator {
+        "+" => Object::from(left + right),
+        "-" => Object::from(left - right),
+        "*" => Object::from(left * right),
+        "/" => {
+            if right.is_zero() {
+                return new_error(format!("division by zero: {} / {}", left, right));
+            }
+            Object::from(left / right)
+        }
+        "%" => {
+            if right.is_zero() {
+                return new_error(format!("division by zero: {} % {}", left, right));
+            }
+            Object::from(left % right)
+        }
+        "<" => native_bool_to_boolean_object(left < right),
+        ">" => native_bool_to_boolean_object(left > right),
+        "<=" => native_bool_to_boolean_object(left <= right),
+        ">=" => native_bool_to_boolean_object(left >= right),
+        "&" => Object::from(left & right),
+        "|" => Object::from(left | right),
+        "^" => Object::from(left ^ right),
+        "<<" | ">>" => eval_big_integer_shift(operator, left, right),
+        "==" => native_bool_to_boolean_object(left == right),
+        "!=" => native_bool_to_boolean_object(left != right),
+        _ => new_error(format!("unknown operator: INTEGER {} INTEGER", operator)),
+    }
+}
+
+/// Shifts with two's complement semantics, so `>>` on a negative number rounds towards negative
+/// infinity just as it does for an i64.
+fn eval_big_integer_shift(operator: &str, left: BigInt, right: BigInt) -> Object {
+    if right.is_negative() {
+        return new_error(format!("negative shift amount: {} {} {}", left, operator, right));
+    }
+    match (operator, right.to_u32()) {
+        ("<<", Some(amount)) => Object::from(left << amount),
+        (">>", Some(amount)) => Object::from(left >> amount),
+        // Nothing is left of a number shifted right by more than u32::MAX bits but its sign.
+        (">>", None) => Object::Integer(if left.is_negative() { -1 } else { 0 }),
+        _ => new_error(format!("shift amount too large: {} {} {}", left, operator, right)),
+    }
+}
+
+fn big_integer_to_f64(value: &BigInt) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Float arithmetic follows IEEE 754, so dividing by zero gives an infinity or NaN rather than an
+/// error.
+fn eval_float_infix_expression(operator: &str, left: f64, right: f64) -> Object {
+    match operator {
+        "+" => Object::Float(left + right),
+        "-" => Object::Float(left - right),
+        "*" => Object::Float(left * right),
+        "/" => Object::Float(left / right),
+        "%" => Object::Float(left % right),
+        "<" => native_bool_to_boolean_object(left < right),
+        ">" => native_bool_to_boolean_object(left > right),
+        "<=" => native_bool_to_boolean_object(left <= right),
+        ">=" => native_bool_to_boolean_object(left >= right),
+        "==" => native_bool_to_boolean_object(left == right),
+        "!=" => native_bool_to_boolean_object(left != right),
+        _ => new_error(format!("unknown operator: FLOAT {} FLOAT", operator)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::environment::Environment;
+    use crate::evaluator::eval_program;
+    use crate::lexer::Lexer;
+    use crate::object::Object;
+    use crate::parser::Parser;
+
+    fn test_eval(input: &str) -> Object {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "parser errors: {:?}", parser.errors());
+        let environment = Rc::new(RefCell::new(Environment::new()));
+        eval_program(&program, &environment)
+    }
+
+    macro_rules! test_eval_integer_expression {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), Object::Integer(expected));
+            }
+        )*
+        }
+    }
+
+    test_eval_integer_expression! {
+        test_eval_integer_expression_1: ("5", 5)
+        test_eval_integer_expression_2: ("10", 10)
+        test_eval_integer_expression_3: ("-5", -5)
+        test_eval_integer_expression_4: ("-10", -10)
+        test_eval_integer_expression_5: ("5 + 5 + 5 + 5 - 10", 10)
+        test_eval_integer_expression_6: ("2 * 2 * 2 * 2 * 2", 32)
+        test_eval_integer_expression_7: ("-50 + 100 + -50", 0)
+        test_eval_integer_expression_8: ("5 * 2 + 10", 20)
+        test_eval_integer_expression_9: ("5 + 2 * 10", 25)
+        test_eval_integer_expression_10: ("20 + 2 * -10", 0)
+        test_eval_integer_expression_11: ("50 / 2 * 2 + 10", 60)
+        test_eval_integer_expression_12: ("3 * 3 * 3 + 10", 37)
+        test_eval_integer_expression_13: ("3 * (3 * 3) + 10", 37)
+        test_eval_integer_expression_14: ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50)
+        test_eval_integer_expression_15: ("0x10 + 0o7 + 0b1 + 1_000", 1024)
+        test_eval_integer_expression_16: ("17 % 5", 2)
+        test_eval_integer_expression_17: ("-17 % 5", -2)
+        test_eval_integer_expression_18: ("1 + 10 % 4 * 3", 7)
+        test_eval_integer_expression_19: ("-9223372036854775808 % -1", 0)
+        test_eval_integer_expression_20: ("12 & 10", 8)
+        test_eval_integer_expression_21: ("12 | 10", 14)
+        test_eval_integer_expression_22: ("12 ^ 10", 6)
+        test_eval_integer_expression_23: ("~0", -1)
+        test_eval_integer_expression_24: ("1 << 4 | 1", 17)
+        test_eval_integer_expression_25: ("-17 >> 2", -5)
+        test_eval_integer_expression_26: ("5 >> 100", 0)
+        test_eval_integer_expression_27: ("0b1100 & ~0b0100", 8)
+    }
+
+    macro_rules! test_eval_big_integer_expression {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input).to_string(), expected);
+            }
+        )*
+        }
+    }
+
+    test_eval_big_integer_expression! {
+        test_eval_big_integer_expression_1: ("9223372036854775807 + 1", "9223372036854775808")
+        test_eval_big_integer_expression_2: ("let a = 9223372036854775807; a * 2",
+            "18446744073709551614")
+        test_eval_big_integer_expression_3: ("-9223372036854775807 - 2", "-9223372036854775809")
+        test_eval_big_integer_expression_4: ("0xffff_ffff_ffff_ffff_ffff",
+            "1208925819614629174706175")
+        test_eval_big_integer_expression_5: ("let double = fn(n) { n * 2 };
+double(double(double(9223372036854775807)));", "73786976294838206456")
+        test_eval_big_integer_expression_6: ("100000000000000000000 * 0.5", "5e19")
+        test_eval_big_integer_expression_7: ("99999999999999999999 > 9223372036854775807", "true")
+        test_eval_big_integer_expression_8: ("99999999999999999999 % 7", "1")
+        test_eval_big_integer_expression_9: ("99999999999999999999 >= 99999999999999999999", "true")
+        test_eval_big_integer_expression_10: ("1 << 64", "18446744073709551616")
+        test_eval_big_integer_expression_11: ("-1 << 63", "-9223372036854775808")
+        test_eval_big_integer_expression_12: ("(1 << 70) >> 69", "2")
+        test_eval_big_integer_expression_13: ("~99999999999999999999", "-100000000000000000000")
+        test_eval_big_integer_expression_14: ("(1 << 64) | 1", "18446744073709551617")
+    }
+
+    #[test]
+    fn test_factorial_100() {
+        let input = "let factorial = fn(n) { if (n < 2) { 1 } else { n * factorial(n - 1) } };
+factorial(100);";
+        assert_eq!(test_eval(input).to_string(), "93326215443944152681699238856266700490715968264\
+381621468592963895217599993229915608941463976156518286253697920827223758251185210916864000000\
+000000000000000000");
+    }
+
+    #[test]
+    fn test_big_integer_demotion() {
+        assert_eq!(test_eval("9223372036854775807 + 1 - 1"), Object::Integer(i64::MAX));
+        assert_eq!(test_eval("-9223372036854775808"), Object::Integer(i64::MIN));
+        assert_eq!(test_eval("99999999999999999999 / 99999999999999999999"), Object::Integer(1));
+    }
+
+    macro_rules! test_eval_float_expression {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), Object::Float(expected));
+            }
+        )*
+        }
+    }
+
+    test_eval_float_expression! {
+        test_eval_float_expression_1: ("2.75", 2.75)
+        test_eval_float_expression_2: ("-.5", -0.5)
+        test_eval_float_expression_3: ("1.5 + 2.25", 3.75)
+        test_eval_float_expression_4: ("1 + .5", 1.5)
+        test_eval_float_expression_5: ("2.5 * 2", 5.0)
+        test_eval_float_expression_6: ("7 / 2.0", 3.5)
+        test_eval_float_expression_7: ("1e3 - 1", 999.0)
+        test_eval_float_expression_8: ("1.0 / 0", f64::INFINITY)
+        test_eval_float_expression_9: ("let half = fn(x) { x / 2.0 }; half(5)", 2.5)
+        test_eval_float_expression_10: ("7.5 % 2", 1.5)
+    }
+
+    #[test]
+    fn test_float_display() {
+        assert_eq!(test_eval("2.5 * 2").to_string(), "5.0");
+        assert_eq!(test_eval("1e-9").to_string(), "1e-9");
+    }
+
+    macro_rules! test_eval_boolean_expression {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), Object::Boolean(expected));
+            }
+        )*
+        }
+    }
+
+    test_eval_boolean_expression! {
+        test_eval_boolean_expression_1: ("1 < 2", true)
+        test_eval_boolean_expression_2: ("1 > 2", false)
+        test_eval_boolean_expression_3: ("1 == 1", true)
+        test_eval_boolean_expression_4: ("1 != 1", false)
+        test_eval_boolean_expression_5: ("1 < 2 == 2 > 1", true)
+        test_eval_boolean_expression_6: ("1 < 2 != 2 < 1", true)
+        test_eval_boolean_expression_7: ("!5", false)
+        test_eval_boolean_expression_8: ("!!5", true)
+        test_eval_boolean_expression_9: ("true", true)
+        test_eval_boolean_expression_10: ("!true", false)
+        test_eval_boolean_expression_11: ("true == false", false)
+        test_eval_boolean_expression_12: ("(1 < 2) == true", true)
+        test_eval_boolean_expression_13: ("(1 > 2) != false", false)
+        test_eval_boolean_expression_14: ("1.5 < 2", true)
+        test_eval_boolean_expression_15: ("1 == 1.0", true)
+        test_eval_boolean_expression_16: (".1 + .2 != .3", true)
+        test_eval_boolean_expression_17: ("1 <= 1", true)
+        test_eval_boolean_expression_18: ("2 <= 1", false)
+        test_eval_boolean_expression_19: ("1 >= 2", false)
+        test_eval_boolean_expression_20: ("2.5 >= 2", true)
+        test_eval_boolean_expression_21: ("true && false", false)
+        test_eval_boolean_expression_22: ("true && 1", true)
+        test_eval_boolean_expression_23: ("false || 0", true)
+        test_eval_boolean_expression_24: ("false || false", false)
+        test_eval_boolean_expression_25: ("1 < 2 && 2 < 3 || false", true)
+        test_eval_boolean_expression_26: ("false && undefined", false)
+        test_eval_boolean_expression_27: ("true || 1 / 0", true)
+    }
+
+    macro_rules! test_if_else_expressions {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), expected);
+            }
+        )*
+        }
+    }
+
+    test_if_else_expressions! {
+        test_if_else_expressions_1: ("if (true) { 10 }", Object::Integer(10))
+        test_if_else_expressions_2: ("if (false) { 10 }", Object::Null)
+        test_if_else_expressions_3: ("if (1) { 10 }", Object::Integer(10))
+        test_if_else_expressions_4: ("if (1 < 2) { 10 }", Object::Integer(10))
+        test_if_else_expressions_5: ("if (1 > 2) { 10 }", Object::Null)
+        test_if_else_expressions_6: ("if (1 > 2) { 10 } else { 20 }", Object::Integer(20))
+        test_if_else_expressions_7: ("if (1 < 2) { 10 } else { 20 }", Object::Integer(10))
+        test_if_else_expressions_8: ("if (1 > 2) { 10 } else if (2 > 1) { 20 } else { 30 }",
+            Object::Integer(20))
+        test_if_else_expressions_9: ("if (1 > 2) { 10 } else if (2 > 3) { 20 } else { 30 }",
+            Object::Integer(30))
+    }
+
+    macro_rules! test_return_statements {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), expected);
+            }
+        )*
+        }
+    }
+
+    test_return_statements! {
+        test_return_statements_1: ("return 10;", Object::Integer(10))
+        test_return_statements_2: ("return 10; 9;", Object::Integer(10))
+        test_return_statements_3: ("return 2 * 5; 9;", Object::Integer(10))
+        test_return_statements_4: ("9; return 2 * 5; 9;", Object::Integer(10))
+        test_return_statements_5: ("return;", Object::Null)
+        test_return_statements_6: ("if (10 > 1) { if (10 > 1) { return 10; } return 1; }",
+            Object::Integer(10))
+    }
+
+    macro_rules! test_let_statements {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), Object::Integer(expected));
+            }
+        )*
+        }
+    }
+
+    test_let_statements! {
+        test_let_statements_1: ("let a = 5; a;", 5)
+        test_let_statements_2: ("let a = 5 * 5; a;", 25)
+        test_let_statements_3: ("let a = 5; let b = a; b;", 5)
+        test_let_statements_4: ("let a = 5; let b = a; let c = a + b + 5; c;", 15)
+        test_let_statements_5: ("const a = 5; let b = a * 2; b;", 10)
+        test_let_statements_6: ("const f = fn(x) { x + 1 }; f(1)", 2)
+        test_let_statements_7: ("let s = 0; for x in [1, 2] { const c = x * 10; s += c; } s", 30)
+        test_let_statements_8: ("const n = 1; let f = fn() { let n = 2; n = 3; n }; f()", 3)
+    }
+
+    macro_rules! test_string_expressions {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), expected);
+            }
+        )*
+        }
+    }
+
+    test_string_expressions! {
+        test_string_expressions_1: (r#""Hello World!""#,
+            Object::String(String::from("Hello World!")))
+        test_string_expressions_2: (r#""Hello" + " " + "World!""#,
+            Object::String(String::from("Hello World!")))
+        test_string_expressions_3: (r#""tab\there\n""#, Object::String(String::from("tab\there\n")))
+        test_string_expressions_4: (r#""a" == "a""#, Object::Boolean(true))
+        test_string_expressions_5: (r#""a" != "a""#, Object::Boolean(false))
+        test_string_expressions_6: (r#""Hello" - "World""#,
+            Object::Error(String::from("unknown operator: STRING - STRING")))
+    }
+
+    #[test]
+    fn test_array_literal() {
+        assert_eq!(test_eval("[1, 2 * 2, 3 + 3]"), Object::Array(vec![
+            Object::Integer(1),
+            Object::Integer(4),
+            Object::Integer(6),
+        ]));
+        assert_eq!(test_eval("[1, [true, \"a\"], 1.5]").to_string(), "[1, [true, a], 1.5]");
+    }
+
+    macro_rules! test_array_index_expressions {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), expected);
+            }
+        )*
+        }
+    }
+
+    test_array_index_expressions! {
+        test_array_index_expressions_1: ("[1, 2, 3][0]", Object::Integer(1))
+        test_array_index_expressions_2: ("[1, 2, 3][1 + 1]", Object::Integer(3))
+        test_array_index_expressions_3: ("let i = 0; [1][i]", Object::Integer(1))
+        test_array_index_expressions_4: ("let a = [1, 2, 3]; a[0] + a[1] + a[2]",
+            Object::Integer(6))
+        test_array_index_expressions_5: ("let f = fn() { [[1, 2], [3]] }; f()[1][0]",
+            Object::Integer(3))
+        test_array_index_expressions_6: ("[1, 2, 3][3]", Object::Null)
+        test_array_index_expressions_7: ("[1, 2, 3][-1]", Object::Null)
+        test_array_index_expressions_8: ("[1][99999999999999999999]", Object::Null)
+        test_array_index_expressions_9: ("[1][\"a\"]",
+            Object::Error(String::from("index operator not supported: ARRAY[STRING]")))
+        test_array_index_expressions_10: ("1[0]",
+            Object::Error(String::from("index operator not supported: INTEGER[INTEGER]")))
+        test_array_index_expressions_11: ("[1, x]",
+            Object::Error(String::from("identifier not found: x")))
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let input = r#"let two = "two";
+{
+    "one": 10 - 9,
+    two: 1 + 1,
+    "thr" + "ee": 6 / 2,
+    4: 4,
+    true: 5,
+    false: 6,
+    99999999999999999999: 7
+}"#;
+        let Object::Hash(hash) = test_eval(input) else {
+            panic!("expected a hash");
+        };
+        let pairs: Vec<(String, i64)> = hash.pairs().iter()
+            .map(|(key, value)| match value {
+                Object::Integer(value) => (key.to_string(), *value),
+                _ => panic!("expected an integer, got {:?}", value),
+            })
+            .collect();
+        assert_eq!(pairs, vec![
+            (String::from("one"), 1),
+            (String::from("two"), 2),
+            (String::from("three"), 3),
+            (String::from("4"), 4),
+            (String::from("true"), 5),
+            (String::from("false"), 6),
+            (String::from("99999999999999999999"), 7),
+        ]);
+    }
+
+    #[test]
+    fn test_hash_display() {
+        assert_eq!(test_eval(r#"{"b": 1, "a": [2], "b": 3}"#).to_string(), "{b: 3, a: [2]}");
+    }
+
+    macro_rules! test_hash_index_expressions {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), expected);
+            }
+        )*
+        }
+    }
+
+    test_hash_index_expressions! {
+        test_hash_index_expressions_1: (r#"{"foo": 5}["foo"]"#, Object::Integer(5))
+        test_hash_index_expressions_2: (r#"{"foo": 5}["bar"]"#, Object::Null)
+        test_hash_index_expressions_3: (r#"let key = "foo"; {"foo": 5}[key]"#, Object::Integer(5))
+        test_hash_index_expressions_4: (r#"{}["foo"]"#, Object::Null)
+        test_hash_index_expressions_5: ("{5: 5}[5]", Object::Integer(5))
+        test_hash_index_expressions_6: ("{true: 5}[true]", Object::Integer(5))
+        test_hash_index_expressions_7: ("{1 << 64: 5}[18446744073709551616]", Object::Integer(5))
+        test_hash_index_expressions_8: ("{1: 5}[1.0]",
+            Object::Error(String::from("unusable as hash key: FLOAT")))
+        test_hash_index_expressions_9: ("{[1]: 5}",
+            Object::Error(String::from("unusable as hash key: ARRAY")))
+        test_hash_index_expressions_10: ("{fn(x) { x }: 5}",
+            Object::Error(String::from("unusable as hash key: FUNCTION")))
+        test_hash_index_expressions_11: ("{1: x}",
+            Object::Error(String::from("identifier not found: x")))
+    }
+
+    macro_rules! test_loop_statements {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), expected);
+            }
+        )*
+        }
+    }
+
+    test_loop_statements! {
+        test_loop_statements_1: ("let i = 0; while (i < 5) { let i = i + 1; }; i",
+            Object::Integer(5))
+        test_loop_statements_2: ("while (false) { 1 }", Object::Null)
+        test_loop_statements_3: ("let s = 0; for x in [1, 2, 3] { let s = s + x; } s",
+            Object::Integer(6))
+        test_loop_statements_4: (r#"let s = ""; for c in "abc" { let s = c + s; } s"#,
+            Object::String(String::from("cba")))
+        test_loop_statements_5: (r#"let s = ""; for k in {"b": 1, "a": 2} { let s = s + k; } s"#,
+            Object::String(String::from("ba")))
+        test_loop_statements_6: ("let i = 0; \
+            while (true) { let i = i + 1; if (i == 3) { break; } } i", Object::Integer(3))
+        test_loop_statements_7: ("let s = 0; \
+            for x in [1, 2, 3, 4] { if (x % 2 == 0) { continue; } let s = s + x; } s",
+            Object::Integer(4))
+        test_loop_statements_8: ("let f = fn() { \
+            for x in [1, 2, 3] { if (x == 2) { return x * 10; } } 0 }; f()", Object::Integer(20))
+        test_loop_statements_9: ("let n = 0; for x in [[1, 2], [3]] { for y in x { break; } \
+            let n = n + 1; } n", Object::Integer(2))
+        test_loop_statements_10: ("for x in [] { x }", Object::Null)
+        test_loop_statements_11: ("for x in 5 { x }",
+            Object::Error(String::from("cannot iterate over INTEGER")))
+        test_loop_statements_12: ("while (y) { 1 }",
+            Object::Error(String::from("identifier not found: y")))
+        test_loop_statements_13: ("for x in [1] { x + true }",
+            Object::Error(String::from("type mismatch: INTEGER + BOOLEAN")))
+        test_loop_statements_14: ("let n = 0; \
+            while (true) { let x = if (true) { break; } else { 1 }; let n = n + 1; } n",
+            Object::Integer(0))
+        test_loop_statements_15: ("let n = 0; \
+            for x in [1, 2, 3] { let a = [x, if (x == 2) { break; } else { x }]; n += a[0]; } n",
+            Object::Integer(1))
+        test_loop_statements_16: ("let s = 0; \
+            for x in [1, 2, 3] { s = if (x == 2) { continue; } else { s + x }; } s",
+            Object::Integer(4))
+        test_loop_statements_17: ("let n = 0; \
+            for x in [1, 2] { n = n + if (true) { continue; } else { 10 }; } n",
+            Object::Integer(0))
+        test_loop_statements_18: ("let f = fn() { for x in [1] { f(if (true) { return 7; }); } }; \
+            f()", Object::Integer(7))
+        test_loop_statements_19: ("let i = 0; while i < 3 { i += 1 } i", Object::Integer(3))
+    }
+
+    macro_rules! test_assign_expressions {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input).to_string(), expected);
+            }
+        )*
+        }
+    }
+
+    test_assign_expressions! {
+        test_assign_expressions_1: ("let x = 1; x = 2; x", "2")
+        test_assign_expressions_2: ("let x = 1; x = 5", "5")
+        test_assign_expressions_3: ("let a = 1; let b = 2; a = b = 3; a + b", "6")
+        test_assign_expressions_4: ("let x = 10; x += 5; x -= 3; x *= 2; x /= 4; x", "6")
+        test_assign_expressions_5: (r#"let s = "a"; s += "b"; s"#, "ab")
+        test_assign_expressions_6: ("let x = 9223372036854775807; x += 1; x", "9223372036854775808")
+        test_assign_expressions_7: ("let a = [1, 2, 3]; a[1] = 20; a", "[1, 20, 3]")
+        test_assign_expressions_8: ("let a = [[1], [2, 3]]; a[1][0] += 5; a", "[[1], [7, 3]]")
+        test_assign_expressions_9: (r#"let h = {"a": 1}; h["b"] = 2; h["a"] *= 10; h"#,
+            "{a: 10, b: 2}")
+        test_assign_expressions_10: (r#"let h = {"xs": [1]}; h["xs"][0] = 2; h"#, "{xs: [2]}")
+        test_assign_expressions_11: ("let a = [1]; let b = a; b[0] = 2; a", "[1]")
+        test_assign_expressions_12: ("let n = 0; let inc = fn() { n += 1 }; inc(); inc(); n", "2")
+        test_assign_expressions_13: ("let n = 0; let f = fn(n) { n = 5 }; f(1); n", "0")
+        test_assign_expressions_14: ("let i = 0; let s = 0; while (i < 4) { i += 1; s += i; } s",
+            "10")
+        test_assign_expressions_15: ("let calls = 0; let f = fn() { calls += 1; 0 }; \
+            let a = [1]; a[f()] += 1; calls", "1")
+        test_assign_expressions_16: ("x = 1", "ERROR: identifier not found: x")
+        test_assign_expressions_17: ("let a = [1]; a[1] = 2", "ERROR: index out of range: 1")
+        test_assign_expressions_18: ("let a = [1]; a[-1] = 2", "ERROR: index out of range: -1")
+        test_assign_expressions_19: ("let a = 1; a[0] = 2",
+            "ERROR: index assignment not supported: INTEGER[INTEGER]")
+        test_assign_expressions_20: ("let h = {}; h[[1]] = 2", "ERROR: unusable as hash key: ARRAY")
+        test_assign_expressions_21: ("let x = true; x += 1",
+            "ERROR: type mismatch: BOOLEAN + INTEGER")
+        test_assign_expressions_22: ("let x = 1; x /= 0", "ERROR: division by zero: 1 / 0")
+        test_assign_expressions_23: ("let h = {}; h[\"a\"][0] = 1",
+            "ERROR: index assignment not supported: NULL[INTEGER]")
+        test_assign_expressions_24: ("let a = [1, 2]; let g = fn() { a[1] = 99; 0 }; a[0] = g(); a",
+            "[0, 99]")
+        test_assign_expressions_25: ("let a = [1, 2]; let g = fn() { a = [5, 6]; 10 }; \
+            a[0] += g(); a", "[11, 6]")
+        test_assign_expressions_26: ("let h = {}; let g = fn() { h[\"b\"] = 2; 1 }; \
+            h[\"a\"] = g(); h", "{b: 2, a: 1}")
+    }
+
+    #[test]
+    fn test_function_object() {
+        let Object::Function(function) = test_eval("fn(x) { x + 2; };") else {
+            panic!("expected a function object");
+        };
+        assert_eq!(function.parameters.len(), 1);
+        assert_eq!(function.parameters[0].to_string(), "x");
+        assert_eq!(function.body.to_string(), "{ (x + 2) }");
+    }
+
+    #[test]
+    fn test_function_body_shared() {
+        let input = "let make = fn() { fn(x) { x } }; [make(), make()]";
+        let Object::Array(functions) = test_eval(input) else {
+            panic!("expected an array");
+        };
+        let [Object::Function(first), Object::Function(second)] = functions.as_slice() else {
+            panic!("expected two functions, got {:?}", functions);
+        };
+        assert!(!Rc::ptr_eq(first, second));
+        assert!(Rc::ptr_eq(&first.body, &second.body));
+        assert!(Rc::ptr_eq(&first.parameters, &second.parameters));
+    }
+
+    macro_rules! test_function_application {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), Object::Integer(expected));
+            }
+        )*
+        }
+    }
+
+    test_function_application! {
+        test_function_application_1: ("let identity = fn(x) { x; }; identity(5);", 5)
+        test_function_application_2: ("let identity = fn(x) { return x; }; identity(5);", 5)
+        test_function_application_3: ("let double = fn(x) { x * 2; }; double(5);", 10)
+        test_function_application_4: ("let add = fn(x, y) { x + y; }; add(5, 5);", 10)
+        test_function_application_5: ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20)
+        test_function_application_6: ("fn(x) { x; }(5)", 5)
+        test_function_application_7: ("let newAdder = fn(x) { fn(y) { x + y } };
+let addTwo = newAdder(2);
+addTwo(2);", 4)
+        test_function_application_8: ("let fib = fn(n) {
+    if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+};
+fib(15);", 610)
+        test_function_application_9: ("let 加 = fn(甲, 乙) { 甲 + 乙 }; 加(1, 2)", 3)
+    }
+
+    macro_rules! test_error_handling {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                assert_eq!(test_eval(input), Object::Error(String::from(expected)));
+            }
+        )*
+        }
+    }
+
+    test_error_handling! {
+        test_error_handling_1: ("let b = 1 > 0; 5 + b;", "type mismatch: INTEGER + BOOLEAN")
+        test_error_handling_2: ("let b = 1 > 0; 5 + b; 5;", "type mismatch: INTEGER + BOOLEAN")
+        test_error_handling_3: ("let b = 1 > 0; -b", "unknown operator: -BOOLEAN")
+        test_error_handling_4: ("let b = 1 > 0; b + b;", "unknown operator: BOOLEAN + BOOLEAN")
+        test_error_handling_5: ("let b = 1 > 0; 5; b * b; 5", "unknown operator: BOOLEAN * BOOLEAN")
+        test_error_handling_6: ("foobar", "identifier not found: foobar")
+        test_error_handling_7: ("99999999999999999999 / 0",
+            "division by zero: 99999999999999999999 / 0")
+        test_error_handling_8: ("-99999999999999999999 + true", "type mismatch: INTEGER + BOOLEAN")
+        test_error_handling_9: ("9223372036854775807 * 2 - \"a\"",
+            "type mismatch: INTEGER - STRING")
+        test_error_handling_10: ("10 / 0", "division by zero: 10 / 0")
+        test_error_handling_11: ("let x = 1 / 0; 5", "division by zero: 1 / 0")
+        test_error_handling_12: ("-true", "unknown operator: -BOOLEAN")
+        test_error_handling_13: ("(5 > 1) + 5", "type mismatch: BOOLEAN + INTEGER")
+        test_error_handling_14: ("if (10 > 1) { true + false; }",
+            "unknown operator: BOOLEAN + BOOLEAN")
+        test_error_handling_15: ("if (10 > 1) { if (10 > 1) { return true + false; } return 1; }",
+            "unknown operator: BOOLEAN + BOOLEAN")
+        test_error_handling_16: ("5(1)", "not a function: INTEGER")
+        test_error_handling_17: ("fn(x, y) { x }(1)",
+            "wrong number of arguments: expected 2, got 1")
+        test_error_handling_18: ("1.5 + true", "type mismatch: FLOAT + BOOLEAN")
+        test_error_handling_19: ("\"a\" * 1.5", "type mismatch: STRING * FLOAT")
+        test_error_handling_20: ("10 % 0", "division by zero: 10 % 0")
+        test_error_handling_21: ("true && undefined", "identifier not found: undefined")
+        test_error_handling_22: ("\"a\" <= \"b\"", "unknown operator: STRING <= STRING")
+        test_error_handling_23: ("1 << -1", "negative shift amount: 1 << -1")
+        test_error_handling_24: ("~1.5", "unknown operator: ~FLOAT")
+        test_error_handling_25: ("1.5 & 1", "unknown operator: FLOAT & INTEGER")
+        test_error_handling_26: ("true | false", "unknown operator: BOOLEAN | BOOLEAN")
+        test_error_handling_27: ("let f = fn() { n = 1 }; const n = 0; f(); n",
+            "cannot assign to const: n")
+        test_error_handling_28: ("let f = fn() { let g = fn() { n = 5 }; g }; \
+            const n = 1; f()(); n", "cannot assign to const: n")
+        test_error_handling_29: ("const xs = [1]; xs[0] += 1", "cannot assign to const: xs")
+        test_error_handling_30: ("const x = 1; let x = 2; x", "cannot assign to const: x")
+        test_error_handling_31: ("const x = 1; for x in [2] { x }", "cannot assign to const: x")
+        test_error_handling_32: ("y = 1", "identifier not found: y")
+        test_error_handling_33: ("1 << 2.0", "unknown operator: INTEGER << FLOAT")
+    }
+}
@@ -1,135 +1,644 @@
-use crate::token::{Token, TokenType};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+use crate::error::LexError;
+use crate::token::{Position, Span, Token, TokenType, Trivia, TriviaKind};
+use unicode_xid::UnicodeXID;
 
-pub struct Lexer {
-    input: String,
-    position: u64,
-    read_position: u64,
+pub struct Lexer<'src> {
+    input: &'src str,
+    /// Offset of the start of `input` within the whole source, which is non-zero when lexing a
+    /// fragment.
+    origin: usize,
+    position: usize,
+    read_position: usize,
     ch: char,
+    line: usize,
+    column: usize,
+    errors: Vec<LexError>,
+    keep_doc_comments: bool,
+    keep_trivia: bool,
+    lookahead: VecDeque<Token<'src>>,
+    finished: bool,
 }
 
-impl Lexer {
-    pub fn new(input: String) -> Self {
+impl<'src> Lexer<'src> {
+    pub fn new(input: &'src str) -> Self {
         let mut lexer = Self {
             input,
+            origin: 0,
             position: 0,
             read_position: 0,
             ch: '\0',
+            line: 1,
+            column: 1,
+            errors: Vec::new(),
+            keep_doc_comments: false,
+            keep_trivia: false,
+            lookahead: VecDeque::new(),
+            finished: false,
         };
         lexer.read_char();
         lexer
     }
 
-    fn is_letter(ch: char) -> bool {
-        'a' <= ch && ch <= 'z' || 'A' <= ch && ch <= 'Z' || ch == '_'
+    /// Emits `///` doc comments as `TokenType::DocComment` tokens instead of skipping them.
+    pub fn with_doc_comments(mut self) -> Self {
+        self.keep_doc_comments = true;
+        self
+    }
+
+    /// Attaches whitespace and comments to each token as leading and trailing trivia, so the
+    /// token stream can reproduce the input exactly.
+    pub fn with_trivia(mut self) -> Self {
+        self.keep_trivia = true;
+        self
+    }
+
+    /// Reports positions as if the input began at `start`, for lexing a fragment of a larger
+    /// source.
+    pub fn starting_at(mut self, start: Position) -> Self {
+        self.origin = start.offset;
+        self.line = start.line;
+        self.column = start.column;
+        self
+    }
+
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Removes and returns the errors reported since the last call.
+    pub fn take_errors(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn is_identifier_start(ch: char) -> bool {
+        ch.is_xid_start() || ch == '_'
+    }
+
+    fn is_identifier_continue(ch: char) -> bool {
+        ch.is_xid_continue()
     }
 
     fn is_digit(ch: char) -> bool {
-        '0' <= ch && ch <= '9'
+        ch.is_ascii_digit()
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
+    /// Consumes whitespace and comments, returning them as trivia when the lexer keeps trivia.
+    /// When `trailing` is set it stops at the first newline.
+    fn skip_trivia(&mut self, trailing: bool) -> Vec<Trivia<'src>> {
+        let mut trivia = Vec::new();
+        loop {
+            let start = self.current_position();
+            let kind = match (self.ch, self.peek_char()) {
+                ('\n', _) | ('\r', '\n') if trailing => break,
+                ('\n', _) => {
+                    self.read_char();
+                    TriviaKind::Newline
+                }
+                ('\r', '\n') => {
+                    self.read_char();
+                    self.read_char();
+                    TriviaKind::Newline
+                }
+                (ch, _) if ch.is_whitespace() => {
+                    while self.ch.is_whitespace() && !self.at_newline() {
+                        self.read_char();
+                    }
+                    TriviaKind::Whitespace
+                }
+                ('/', '/') if self.at_doc_comment() => {
+                    if self.keep_doc_comments {
+                        break;
+                    }
+                    self.skip_line_comment();
+                    TriviaKind::DocComment
+                }
+                ('/', '/') => {
+                    self.skip_line_comment();
+                    TriviaKind::LineComment
+                }
+                ('/', '*') => {
+                    self.skip_block_comment();
+                    TriviaKind::BlockComment
+                }
+                _ => break,
+            };
+            if self.keep_trivia {
+                trivia.push(Trivia {
+                    kind,
+                    text: Cow::Borrowed(self.source_from(start)),
+                    span: Span::new(start, self.current_position()),
+                });
+            }
+        }
+        trivia
+    }
+
+    fn at_newline(&self) -> bool {
+        self.ch == '\n' || self.ch == '\r' && self.peek_char() == '\n'
+    }
+
+    /// Whether the lexer is on a `///` doc comment. Four or more slashes are a plain comment.
+    fn at_doc_comment(&self) -> bool {
+        let rest = &self.input[self.position..];
+        rest.starts_with("///") && !rest.starts_with("////")
+    }
+
+    fn skip_line_comment(&mut self) {
+        while !self.at_newline() && !self.at_end() {
             self.read_char();
         }
     }
 
+    /// Skips a block comment, which may contain nested block comments.
+    fn skip_block_comment(&mut self) {
+        let start = self.current_position();
+        let mut depth = 0;
+        loop {
+            match (self.ch, self.peek_char()) {
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    if depth == 0 {
+                        self.read_char();
+                        return;
+                    }
+                }
+                _ if self.at_end() => {
+                    self.errors.push(LexError::UnterminatedComment {
+                        span: Span::new(start, self.current_position()),
+                    });
+                    return;
+                }
+                _ => {}
+            }
+            self.read_char();
+        }
+    }
+
+    /// Reads a doc comment, returning its text without the `///` and one following space.
+    fn read_doc_comment(&mut self) -> &'src str {
+        let position = self.position + "///".len();
+        self.skip_line_comment();
+        let text = &self.input[position..self.position];
+        text.strip_prefix(' ').unwrap_or(text)
+    }
+
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() as u64 {
-            self.ch = '\0';
-        } else {
-            self.ch = self.input.as_bytes()[self.read_position as usize] as char
+        if self.read_position > 0 && self.position < self.input.len() {
+            if self.ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+
+        match self.input[self.read_position.min(self.input.len())..].chars().next() {
+            Some(ch) => {
+                self.ch = ch;
+                self.position = self.read_position;
+                self.read_position += ch.len_utf8();
+            }
+            None => {
+                self.ch = '\0';
+                self.position = self.input.len();
+                self.read_position = self.position + 1;
+            }
         }
-        self.position = self.read_position;
-        self.read_position += 1;
     }
 
-    fn peek_char(&mut self) -> char {
-        if self.read_position >= self.input.len() as u64 {
+    fn peek_char(&self) -> char {
+        if self.read_position >= self.input.len() {
             return '\0'
         }
-        self.input.as_bytes()[self.read_position as usize] as char
+        self.input[self.read_position..].chars().next().unwrap_or('\0')
+    }
+
+    pub(crate) fn current_position(&self) -> Position {
+        Position::new(self.origin + self.position, self.line, self.column)
+    }
+
+    /// The input from `start` up to the current character.
+    fn source_from(&self, start: Position) -> &'src str {
+        &self.input[start.offset - self.origin..self.position]
+    }
+
+    /// The span covering just the current character.
+    fn current_char_span(&self) -> Span {
+        let start = self.current_position();
+        let end = Position::new(start.offset + self.ch.len_utf8(), start.line, start.column + 1);
+        Span::new(start, end)
     }
 
-    fn read_identifier(&mut self) -> String {
+    fn read_identifier(&mut self) -> &'src str {
         let position = self.position;
-        while Self::is_letter(self.ch) {
+        self.read_char();
+        while Self::is_identifier_continue(self.ch) {
             self.read_char();
         }
-        self.input[position as usize..self.position as usize].to_string()
+        &self.input[position..self.position]
     }
 
-    fn read_number(&mut self) -> String {
-        let position = self.position;
-        while Self::is_digit(self.ch) {
+    /// Reads an integer or float literal such as `42`, `1_000`, `0xff`, `3.14`, `.5` or `1e-9`.
+    /// A literal that runs on into another fraction or has an exponent without digits, like
+    /// `1.2.3` or `1e`, is read whole and reported as malformed.
+    fn read_number(&mut self) -> (TokenType, &'src str) {
+        let start = self.current_position();
+        if self.ch == '0' && matches!(self.peek_char(), 'x' | 'o' | 'b') {
+            return self.read_prefixed_integer(start);
+        }
+        let mut token_type = TokenType::Int;
+        self.read_digits();
+        if self.ch == '.' && Self::is_digit(self.peek_char()) {
+            token_type = TokenType::Float;
+            self.read_char();
+            self.read_digits();
+        }
+        let mut malformed = false;
+        if matches!(self.ch, 'e' | 'E') {
+            token_type = TokenType::Float;
             self.read_char();
+            if matches!(self.ch, '+' | '-') {
+                self.read_char();
+            }
+            malformed = !Self::is_digit(self.ch);
+            self.read_digits();
+        }
+        if malformed || self.ch == '.' && Self::is_digit(self.peek_char()) {
+            return self.read_malformed_number(start);
         }
-        self.input[position as usize..self.position as usize].to_string()
+        (token_type, self.source_from(start))
     }
 
-    pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
-        let mut token = Token { token_type: TokenType::EOF, literal: String::from(self.ch) };
-        match self.ch {
-            '=' => {
-                if self.peek_char() == '=' {
-                    let ch = self.ch;
-                    self.read_char();
-                    token.token_type = TokenType::EQ;
-                    token.literal = ch.to_string() + &self.ch.to_string();
-                } else {
-                    token.token_type = TokenType::Assign;
+    /// Reads a `0x`, `0o` or `0b` integer literal, reporting the first digit that is not valid
+    /// in its radix.
+    fn read_prefixed_integer(&mut self, start: Position) -> (TokenType, &'src str) {
+        self.read_char();
+        let radix = match self.ch {
+            'x' => 16,
+            'o' => 8,
+            _ => 2,
+        };
+        self.read_char();
+        let mut has_digits = false;
+        let mut invalid_digit = None;
+        while Self::is_identifier_continue(self.ch) {
+            if self.ch.is_digit(radix) {
+                has_digits = true;
+            } else if self.ch != '_' && invalid_digit.is_none() {
+                invalid_digit = Some(LexError::InvalidDigit {
+                    digit: self.ch,
+                    radix,
+                    span: self.current_char_span(),
+                });
+            }
+            self.read_char();
+        }
+        if !has_digits || self.ch == '.' && Self::is_digit(self.peek_char()) {
+            return self.read_malformed_number(start);
+        }
+        if let Some(error) = invalid_digit {
+            self.errors.push(error);
+            return (TokenType::Illegal, self.source_from(start));
+        }
+        (TokenType::Int, self.source_from(start))
+    }
+
+    /// Reads the rest of a malformed number literal so it is reported as a single error.
+    fn read_malformed_number(&mut self, start: Position) -> (TokenType, &'src str) {
+        while self.ch == '.' || Self::is_identifier_continue(self.ch) {
+            self.read_char();
+        }
+        let literal = self.source_from(start);
+        self.errors.push(LexError::MalformedNumber {
+            literal: literal.to_string(),
+            span: Span::new(start, self.current_position()),
+        });
+        (TokenType::Illegal, literal)
+    }
+
+    /// Reads decimal digits and `_` separators.
+    fn read_digits(&mut self) {
+        while Self::is_digit(self.ch) || self.ch == '_' {
+            self.read_char();
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    /// Reads a string literal starting at the opening quote, decoding escape sequences. Leaves
+    /// the lexer on the closing quote, or returns `None` if the input ends first. The value
+    /// borrows from the input unless it contains an escape sequence.
+    fn read_string(&mut self) -> Option<Cow<'src, str>> {
+        let position = self.position + 1;
+        let mut decoded: Option<String> = None;
+        loop {
+            self.read_char();
+            match self.ch {
+                '"' => {
+                    return Some(match decoded {
+                        Some(value) => Cow::Owned(value),
+                        None => Cow::Borrowed(&self.input[position..self.position]),
+                    });
                 }
-            },
-            '+' => token.token_type = TokenType::Plus,
-            '-' => token.token_type = TokenType::Minus,
-            '!' => {
-                if self.peek_char() == '=' {
-                    let ch = self.ch;
-                    self.read_char();
-                    token.token_type = TokenType::NotEQ;
-                    token.literal = ch.to_string() + &self.ch.to_string();
-                } else {
-                    token.token_type = TokenType::Bang;
+                _ if self.at_end() => return None,
+                '\\' => {
+                    let value = decoded.get_or_insert_with(|| {
+                        self.input[position..self.position].to_string()
+                    });
+                    if let Some(ch) = self.read_escape() {
+                        value.push(ch);
+                    }
                 }
-            },
-            '*' => token.token_type = TokenType::Asterisk,
-            '/' => token.token_type = TokenType::Slash,
-            '<' => token.token_type = TokenType::LT,
-            '>' => token.token_type = TokenType::GT,
-            ';' => token.token_type = TokenType::Semicolon,
-            '(' => token.token_type = TokenType::LParen,
-            ')' => token.token_type = TokenType::RParen,
-            ',' => token.token_type = TokenType::Comma,
-            '{' => token.token_type = TokenType::LBrace,
-            '}' => token.token_type = TokenType::RBrace,
-            '\0' => token.literal = String::from(""),
-            _ => {
-                if Self::is_letter(self.ch) {
-                    token.literal = self.read_identifier();
-                    token.token_type = TokenType::lookup_identifier(&token.literal);
-                    return token;
-                } else if Self::is_digit(self.ch) {
-                    token.token_type = TokenType::Int;
-                    token.literal = self.read_number();
-                    return token;
-                } else {
-                    token.token_type = TokenType::Illegal;
+                ch => {
+                    if let Some(value) = decoded.as_mut() {
+                        value.push(ch);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads the escape sequence following a backslash, reporting an error if it is invalid.
+    fn read_escape(&mut self) -> Option<char> {
+        let start = self.current_position();
+        let escaped = match self.peek_char() {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            '\\' => Some('\\'),
+            '"' => Some('"'),
+            'u' => {
+                self.read_char();
+                return self.read_unicode_escape(start);
+            }
+            _ => None,
+        };
+        match escaped {
+            Some(ch) => {
+                self.read_char();
+                Some(ch)
+            }
+            None => {
+                let mut sequence = String::from("\\");
+                if self.read_position < self.input.len() {
+                    self.read_char();
+                    sequence.push(self.ch);
                 }
+                self.invalid_escape(sequence, start);
+                None
             }
         }
+    }
+
+    /// Reads the `{...}` part of a `\u{...}` escape, with the lexer on the `u`.
+    fn read_unicode_escape(&mut self, start: Position) -> Option<char> {
+        let mut sequence = String::from("\\u");
+        if self.peek_char() != '{' {
+            self.invalid_escape(sequence, start);
+            return None;
+        }
+        self.read_char();
+        sequence.push('{');
+
+        let mut digits = String::new();
+        while self.peek_char().is_ascii_hexdigit() && digits.len() < 6 {
+            self.read_char();
+            digits.push(self.ch);
+        }
+        sequence.push_str(&digits);
+        if self.peek_char() != '}' {
+            self.invalid_escape(sequence, start);
+            return None;
+        }
+        self.read_char();
+        sequence.push('}');
+
+        let ch = u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32);
+        if ch.is_none() {
+            self.invalid_escape(sequence, start);
+        }
+        ch
+    }
+
+    fn invalid_escape(&mut self, sequence: String, start: Position) {
+        self.errors.push(LexError::InvalidEscape {
+            sequence,
+            span: Span::new(start, self.current_char_span().end),
+        });
+    }
+
+    pub fn next_token(&mut self) -> Token<'src> {
+        match self.lookahead.pop_front() {
+            Some(token) => token,
+            None => self.lex_token(),
+        }
+    }
+
+    /// Returns the token `n` places ahead without consuming it, so `peek_nth(0)` is the token the
+    /// next call to `next_token` returns. Errors for peeked tokens are reported as soon as they
+    /// are lexed.
+    pub fn peek_nth(&mut self, n: usize) -> &Token<'src> {
+        while self.lookahead.len() <= n {
+            let token = self.lex_token();
+            self.lookahead.push_back(token);
+        }
+        &self.lookahead[n]
+    }
+
+    pub fn peek(&mut self) -> &Token<'src> {
+        self.peek_nth(0)
+    }
+
+    fn lex_token(&mut self) -> Token<'src> {
+        let leading_trivia = self.skip_trivia(false);
+        let mut token = self.read_token();
+        if self.keep_trivia {
+            token.leading_trivia = leading_trivia;
+            token.trailing_trivia = self.skip_trivia(true);
+        }
+        token
+    }
+
+    fn read_token(&mut self) -> Token<'src> {
+        let start = self.current_position();
+        let mut literal = None;
+        let token_type = match self.ch {
+            '=' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::EQ
+            }
+            '=' => TokenType::Assign,
+            '+' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::PlusAssign
+            }
+            '+' => TokenType::Plus,
+            '-' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::MinusAssign
+            }
+            '-' => TokenType::Minus,
+            '!' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::NotEQ
+            }
+            '!' => TokenType::Bang,
+            '*' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::AsteriskAssign
+            }
+            '*' => TokenType::Asterisk,
+            '/' if self.keep_doc_comments && self.at_doc_comment() => {
+                let text = self.read_doc_comment();
+                let span = Span::new(start, self.current_position());
+                return Token::with_span(TokenType::DocComment, text, span);
+            }
+            '/' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::SlashAssign
+            }
+            '/' => TokenType::Slash,
+            '%' => TokenType::Percent,
+            '<' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::LTEQ
+            }
+            '<' if self.peek_char() == '<' => {
+                self.read_char();
+                TokenType::ShiftLeft
+            }
+            '<' => TokenType::LT,
+            '>' if self.peek_char() == '=' => {
+                self.read_char();
+                TokenType::GTEQ
+            }
+            '>' if self.peek_char() == '>' => {
+                self.read_char();
+                TokenType::ShiftRight
+            }
+            '>' => TokenType::GT,
+            '&' if self.peek_char() == '&' => {
+                self.read_char();
+                TokenType::And
+            }
+            '&' => TokenType::Ampersand,
+            '|' if self.peek_char() == '|' => {
+                self.read_char();
+                TokenType::Or
+            }
+            '|' => TokenType::Pipe,
+            '^' => TokenType::Caret,
+            '~' => TokenType::Tilde,
+            ';' => TokenType::Semicolon,
+            ':' => TokenType::Colon,
+            '(' => TokenType::LParen,
+            ')' => TokenType::RParen,
+            ',' => TokenType::Comma,
+            '{' => TokenType::LBrace,
+            '}' => TokenType::RBrace,
+            '[' => TokenType::LBracket,
+            ']' => TokenType::RBracket,
+            '"' => match self.read_string() {
+                Some(value) => {
+                    literal = Some(value);
+                    TokenType::String
+                }
+                None => {
+                    let span = Span::new(start, self.current_position());
+                    self.errors.push(LexError::UnterminatedString { span });
+                    let text = self.source_from(start);
+                    return Token::with_span(TokenType::Illegal, text, span);
+                }
+            },
+            '\0' if self.at_end() => TokenType::EOF,
+            ch if Self::is_identifier_start(ch) => {
+                let identifier = self.read_identifier();
+                let span = Span::new(start, self.current_position());
+                let token_type = TokenType::lookup_identifier(identifier);
+                return Token::with_span(token_type, identifier, span);
+            }
+            ch if Self::is_digit(ch) || ch == '.' && Self::is_digit(self.peek_char()) => {
+                let (token_type, number) = self.read_number();
+                let span = Span::new(start, self.current_position());
+                return Token::with_span(token_type, number, span);
+            }
+            ch => {
+                self.errors.push(LexError::IllegalCharacter {
+                    ch,
+                    span: self.current_char_span(),
+                });
+                TokenType::Illegal
+            }
+        };
         self.read_char();
-        return token;
+        let literal = literal.unwrap_or(Cow::Borrowed(self.source_from(start)));
+        Token::with_span(token_type, literal, Span::new(start, self.current_position()))
+    }
+}
+
+/// Anything the parser can pull tokens from.
+pub trait TokenSource<'src> {
+    /// Returns the next token. Once the input is exhausted every call returns `EOF`.
+    fn next_token(&mut self) -> Token<'src>;
+    /// Removes and returns the errors reported since the last call.
+    fn take_errors(&mut self) -> Vec<LexError>;
+}
+
+impl<'src> TokenSource<'src> for Lexer<'src> {
+    fn next_token(&mut self) -> Token<'src> {
+        Lexer::next_token(self)
+    }
+
+    fn take_errors(&mut self) -> Vec<LexError> {
+        Lexer::take_errors(self)
+    }
+}
+
+/// Yields every token up to and including `EOF`, which is yielded exactly once.
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Token<'src>;
+
+    fn next(&mut self) -> Option<Token<'src>> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = token.token_type == TokenType::EOF;
+        Some(token)
+    }
+}
+
+impl FusedIterator for Lexer<'_> {}
+
+/// Lexes `input` in one go, returning every token including the final `EOF`, or the first error.
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>, LexError> {
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.by_ref().collect();
+    match lexer.take_errors().into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(tokens),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::lexer::Lexer;
-    use crate::token::TokenType;
+    use std::borrow::Cow;
+    use crate::error::LexError;
+    use crate::lexer::{tokenize, Lexer};
+    use crate::token::{Position, Span, TokenType, Trivia, TriviaKind};
 
     #[test]
     fn test_next_token() {
-        let input = String::from("let five = 5;
+        let input = "let five = 5;
 let ten = 10;
 
 let add = fn(x, y) {
@@ -137,7 +646,7 @@ let add = fn(x, y) {
 };
 
 let result = add(five, ten);
-!-/*5;
+!-/ *5;
 5 < 10 > 5;
 
 if (5 < 10) {
@@ -147,7 +656,15 @@ if (5 < 10) {
 }
 
 10 == 10;
-10 != 9;");
+10 != 9;
+a <= b >= c % d && e || f;
+~a & b | c ^ d << 1 >> 2;
+[1, 2][0];
+{\"a\": 1};
+while for in break continue const;
+x += 1 -= 2 *= 3 /= 4;
+\"foobar\"
+\"foo bar\"";
 
         struct LexerTest {
             expected_type: TokenType,
@@ -237,6 +754,65 @@ if (5 < 10) {
             LexerTest::new(TokenType::NotEQ, "!="),
             LexerTest::new(TokenType::Int, "9"),
             LexerTest::new(TokenType::Semicolon, ";"),
+            LexerTest::new(TokenType::Ident, "a"),
+            LexerTest::new(TokenType::LTEQ, "<="),
+            LexerTest::new(TokenType::Ident, "b"),
+            LexerTest::new(TokenType::GTEQ, ">="),
+            LexerTest::new(TokenType::Ident, "c"),
+            LexerTest::new(TokenType::Percent, "%"),
+            LexerTest::new(TokenType::Ident, "d"),
+            LexerTest::new(TokenType::And, "&&"),
+            LexerTest::new(TokenType::Ident, "e"),
+            LexerTest::new(TokenType::Or, "||"),
+            LexerTest::new(TokenType::Ident, "f"),
+            LexerTest::new(TokenType::Semicolon, ";"),
+            LexerTest::new(TokenType::Tilde, "~"),
+            LexerTest::new(TokenType::Ident, "a"),
+            LexerTest::new(TokenType::Ampersand, "&"),
+            LexerTest::new(TokenType::Ident, "b"),
+            LexerTest::new(TokenType::Pipe, "|"),
+            LexerTest::new(TokenType::Ident, "c"),
+            LexerTest::new(TokenType::Caret, "^"),
+            LexerTest::new(TokenType::Ident, "d"),
+            LexerTest::new(TokenType::ShiftLeft, "<<"),
+            LexerTest::new(TokenType::Int, "1"),
+            LexerTest::new(TokenType::ShiftRight, ">>"),
+            LexerTest::new(TokenType::Int, "2"),
+            LexerTest::new(TokenType::Semicolon, ";"),
+            LexerTest::new(TokenType::LBracket, "["),
+            LexerTest::new(TokenType::Int, "1"),
+            LexerTest::new(TokenType::Comma, ","),
+            LexerTest::new(TokenType::Int, "2"),
+            LexerTest::new(TokenType::RBracket, "]"),
+            LexerTest::new(TokenType::LBracket, "["),
+            LexerTest::new(TokenType::Int, "0"),
+            LexerTest::new(TokenType::RBracket, "]"),
+            LexerTest::new(TokenType::Semicolon, ";"),
+            LexerTest::new(TokenType::LBrace, "{"),
+            LexerTest::new(TokenType::String, "a"),
+            LexerTest::new(TokenType::Colon, ":"),
+            LexerTest::new(TokenType::Int, "1"),
+            LexerTest::new(TokenType::RBrace, "}"),
+            LexerTest::new(TokenType::Semicolon, ";"),
+            LexerTest::new(TokenType::While, "while"),
+            LexerTest::new(TokenType::For, "for"),
+            LexerTest::new(TokenType::In, "in"),
+            LexerTest::new(TokenType::Break, "break"),
+            LexerTest::new(TokenType::Continue, "continue"),
+            LexerTest::new(TokenType::Const, "const"),
+            LexerTest::new(TokenType::Semicolon, ";"),
+            LexerTest::new(TokenType::Ident, "x"),
+            LexerTest::new(TokenType::PlusAssign, "+="),
+            LexerTest::new(TokenType::Int, "1"),
+            LexerTest::new(TokenType::MinusAssign, "-="),
+            LexerTest::new(TokenType::Int, "2"),
+            LexerTest::new(TokenType::AsteriskAssign, "*="),
+            LexerTest::new(TokenType::Int, "3"),
+            LexerTest::new(TokenType::SlashAssign, "/="),
+            LexerTest::new(TokenType::Int, "4"),
+            LexerTest::new(TokenType::Semicolon, ";"),
+            LexerTest::new(TokenType::String, "foobar"),
+            LexerTest::new(TokenType::String, "foo bar"),
             LexerTest::new(TokenType::EOF, ""),
         ];
 
@@ -247,4 +823,384 @@ if (5 < 10) {
             assert_eq!(token.literal, test.expected_literal);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_token_spans() {
+        let input = "let x = 10;
+x != 5";
+
+        let tests = [
+            (TokenType::Let, Span::new(Position::new(0, 1, 1), Position::new(3, 1, 4))),
+            (TokenType::Ident, Span::new(Position::new(4, 1, 5), Position::new(5, 1, 6))),
+            (TokenType::Assign, Span::new(Position::new(6, 1, 7), Position::new(7, 1, 8))),
+            (TokenType::Int, Span::new(Position::new(8, 1, 9), Position::new(10, 1, 11))),
+            (TokenType::Semicolon, Span::new(Position::new(10, 1, 11), Position::new(11, 1, 12))),
+            (TokenType::Ident, Span::new(Position::new(12, 2, 1), Position::new(13, 2, 2))),
+            (TokenType::NotEQ, Span::new(Position::new(14, 2, 3), Position::new(16, 2, 5))),
+            (TokenType::Int, Span::new(Position::new(17, 2, 6), Position::new(18, 2, 7))),
+            (TokenType::EOF, Span::new(Position::new(18, 2, 7), Position::new(18, 2, 7))),
+            (TokenType::EOF, Span::new(Position::new(18, 2, 7), Position::new(18, 2, 7))),
+        ];
+
+        let mut lexer = Lexer::new(input);
+        for (expected_type, expected_span) in tests {
+            let token = lexer.next_token();
+            assert_eq!(token.token_type, expected_type);
+            assert_eq!(token.span, expected_span);
+        }
+    }
+
+    macro_rules! test_string_escapes {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+
+                let mut lexer = Lexer::new(input);
+                let token = lexer.next_token();
+                assert!(lexer.errors().is_empty(), "lexer errors: {:?}", lexer.errors());
+                assert_eq!(token.token_type, TokenType::String);
+                assert_eq!(token.literal, expected);
+                assert_eq!(token.span.len(), input.len());
+            }
+        )*
+        }
+    }
+
+    test_string_escapes! {
+        test_string_escapes_1: (r#""a\nb""#, "a\nb")
+        test_string_escapes_2: (r#""a\tb""#, "a\tb")
+        test_string_escapes_3: (r#""a\\b""#, "a\\b")
+        test_string_escapes_4: (r#""say \"hi\"""#, "say \"hi\"")
+        test_string_escapes_5: (r#""\u{41}\u{1F600}""#, "A\u{1F600}")
+        test_string_escapes_6: (r#""""#, "")
+    }
+
+    macro_rules! test_lexer_errors {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+
+                let mut lexer = Lexer::new(input);
+                while lexer.next_token().token_type != TokenType::EOF {}
+                assert_eq!(lexer.errors(), &[expected]);
+            }
+        )*
+        }
+    }
+
+    test_lexer_errors! {
+        test_lexer_errors_1: ("let s = \"abc", LexError::UnterminatedString {
+            span: Span::new(Position::new(8, 1, 9), Position::new(12, 1, 13)),
+        })
+        test_lexer_errors_2: ("\"a\\qb\"", LexError::InvalidEscape {
+            sequence: String::from("\\q"),
+            span: Span::new(Position::new(2, 1, 3), Position::new(4, 1, 5)),
+        })
+        test_lexer_errors_3: ("\"\\u{110000}\"", LexError::InvalidEscape {
+            sequence: String::from("\\u{110000}"),
+            span: Span::new(Position::new(1, 1, 2), Position::new(11, 1, 12)),
+        })
+        test_lexer_errors_4: ("\"\\u41\"", LexError::InvalidEscape {
+            sequence: String::from("\\u"),
+            span: Span::new(Position::new(1, 1, 2), Position::new(3, 1, 4)),
+        })
+        test_lexer_errors_5: ("1 @ 2", LexError::IllegalCharacter {
+            ch: '@',
+            span: Span::new(Position::new(2, 1, 3), Position::new(3, 1, 4)),
+        })
+        test_lexer_errors_6: ("x = 1.2.3;", LexError::MalformedNumber {
+            literal: String::from("1.2.3"),
+            span: Span::new(Position::new(4, 1, 5), Position::new(9, 1, 10)),
+        })
+        test_lexer_errors_7: ("1e+ 2", LexError::MalformedNumber {
+            literal: String::from("1e+"),
+            span: Span::new(Position::new(0, 1, 1), Position::new(3, 1, 4)),
+        })
+        test_lexer_errors_8: ("2.5e3.1", LexError::MalformedNumber {
+            literal: String::from("2.5e3.1"),
+            span: Span::new(Position::new(0, 1, 1), Position::new(7, 1, 8)),
+        })
+        test_lexer_errors_9: ("0b1021", LexError::InvalidDigit {
+            digit: '2',
+            radix: 2,
+            span: Span::new(Position::new(4, 1, 5), Position::new(5, 1, 6)),
+        })
+        test_lexer_errors_10: ("x = 0o79;", LexError::InvalidDigit {
+            digit: '9',
+            radix: 8,
+            span: Span::new(Position::new(7, 1, 8), Position::new(8, 1, 9)),
+        })
+        test_lexer_errors_11: ("0x;", LexError::MalformedNumber {
+            literal: String::from("0x"),
+            span: Span::new(Position::new(0, 1, 1), Position::new(2, 1, 3)),
+        })
+        test_lexer_errors_12: ("0xff.8", LexError::MalformedNumber {
+            literal: String::from("0xff.8"),
+            span: Span::new(Position::new(0, 1, 1), Position::new(6, 1, 7)),
+        })
+        test_lexer_errors_13: ("1;\0 2", LexError::IllegalCharacter {
+            ch: '\0',
+            span: Span::new(Position::new(2, 1, 3), Position::new(3, 1, 4)),
+        })
+    }
+
+    macro_rules! test_number_literals {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+
+                let tokens: Vec<(TokenType, String)> = tokenize(input).unwrap().into_iter()
+                    .map(|token| (token.token_type, token.literal.to_string()))
+                    .collect();
+                let expected: Vec<(TokenType, String)> = expected.into_iter()
+                    .map(|(token_type, literal): (TokenType, &str)| {
+                        (token_type, literal.to_string())
+                    })
+                    .chain([(TokenType::EOF, String::new())])
+                    .collect();
+                assert_eq!(tokens, expected);
+            }
+        )*
+        }
+    }
+
+    test_number_literals! {
+        test_number_literals_1: ("3.14", vec![(TokenType::Float, "3.14")])
+        test_number_literals_2: (".5", vec![(TokenType::Float, ".5")])
+        test_number_literals_3: ("1e-9", vec![(TokenType::Float, "1e-9")])
+        test_number_literals_4: ("6.02E+23", vec![(TokenType::Float, "6.02E+23")])
+        test_number_literals_5: ("42", vec![(TokenType::Int, "42")])
+        test_number_literals_6: ("1 -.5", vec![
+            (TokenType::Int, "1"),
+            (TokenType::Minus, "-"),
+            (TokenType::Float, ".5"),
+        ])
+        test_number_literals_7: ("0xFF_ff", vec![(TokenType::Int, "0xFF_ff")])
+        test_number_literals_8: ("0o755", vec![(TokenType::Int, "0o755")])
+        test_number_literals_9: ("0b1010_0101", vec![(TokenType::Int, "0b1010_0101")])
+        test_number_literals_10: ("1_000_000", vec![(TokenType::Int, "1_000_000")])
+        test_number_literals_11: ("1_000.000_1", vec![(TokenType::Float, "1_000.000_1")])
+    }
+
+    #[test]
+    fn test_unicode_input() {
+        let input = "let café = \"naïve 日本\";\n变量 + _ñ1;\n😀";
+
+        let span = |start: (usize, usize, usize), end: (usize, usize, usize)| Span::new(
+            Position::new(start.0, start.1, start.2), Position::new(end.0, end.1, end.2));
+        let tests = [
+            (TokenType::Let, "let", span((0, 1, 1), (3, 1, 4))),
+            (TokenType::Ident, "café", span((4, 1, 5), (9, 1, 9))),
+            (TokenType::Assign, "=", span((10, 1, 10), (11, 1, 11))),
+            (TokenType::String, "naïve 日本", span((12, 1, 12), (27, 1, 22))),
+            (TokenType::Semicolon, ";", span((27, 1, 22), (28, 1, 23))),
+            (TokenType::Ident, "变量", span((29, 2, 1), (35, 2, 3))),
+            (TokenType::Plus, "+", span((36, 2, 4), (37, 2, 5))),
+            (TokenType::Ident, "_ñ1", span((38, 2, 6), (42, 2, 9))),
+            (TokenType::Semicolon, ";", span((42, 2, 9), (43, 2, 10))),
+            (TokenType::Illegal, "😀", span((44, 3, 1), (48, 3, 2))),
+            (TokenType::EOF, "", span((48, 3, 2), (48, 3, 2))),
+        ];
+
+        let mut lexer = Lexer::new(input);
+        for (expected_type, expected_literal, expected_span) in tests {
+            let token = lexer.next_token();
+            assert_eq!(token.token_type, expected_type);
+            assert_eq!(token.literal, expected_literal);
+            assert_eq!(token.span, expected_span);
+        }
+        assert_eq!(lexer.errors(), &[LexError::IllegalCharacter {
+            ch: '😀',
+            span: span((44, 3, 1), (48, 3, 2)),
+        }]);
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "// line comment
+let a = 1; // trailing
+/* block */ let /* inline */ b = a / 2;
+/* outer /* nested */ still a comment */
+//// four slashes
+/// doc comment
+a * b";
+
+        let tests = [
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "a"),
+            (TokenType::Assign, "="),
+            (TokenType::Int, "1"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "b"),
+            (TokenType::Assign, "="),
+            (TokenType::Ident, "a"),
+            (TokenType::Slash, "/"),
+            (TokenType::Int, "2"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::Ident, "a"),
+            (TokenType::Asterisk, "*"),
+            (TokenType::Ident, "b"),
+            (TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+        for (expected_type, expected_literal) in tests {
+            let token = lexer.next_token();
+            assert_eq!(token.token_type, expected_type);
+            assert_eq!(token.literal, expected_literal);
+        }
+        assert!(lexer.errors().is_empty());
+    }
+
+    #[test]
+    fn test_doc_comment_tokens() {
+        let input = "/// Adds things.\r\n///no space\n//// not docs\nlet";
+
+        let mut lexer = Lexer::new(input).with_doc_comments();
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::DocComment);
+        assert_eq!(token.literal, "Adds things.");
+        assert_eq!(token.span, Span::new(Position::new(0, 1, 1), Position::new(16, 1, 17)));
+
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::DocComment);
+        assert_eq!(token.literal, "no space");
+
+        assert_eq!(lexer.next_token().token_type, TokenType::Let);
+        assert_eq!(lexer.next_token().token_type, TokenType::EOF);
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let mut lexer = Lexer::new("1 /* a /* b */");
+        assert_eq!(lexer.next_token().token_type, TokenType::Int);
+        assert_eq!(lexer.next_token().token_type, TokenType::EOF);
+        assert_eq!(lexer.errors(), &[LexError::UnterminatedComment {
+            span: Span::new(Position::new(2, 1, 3), Position::new(14, 1, 15)),
+        }]);
+    }
+
+    #[test]
+    fn test_trivia() {
+        let input = "  let x // x\r\n/* y */ = 1;\n";
+
+        let mut lexer = Lexer::new(input).with_trivia();
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::Let);
+        assert_eq!(token.leading_trivia, vec![Trivia {
+            kind: TriviaKind::Whitespace,
+            text: "  ".into(),
+            span: Span::new(Position::new(0, 1, 1), Position::new(2, 1, 3)),
+        }]);
+        assert_eq!(token.trailing_trivia[0].kind, TriviaKind::Whitespace);
+
+        let token = lexer.next_token();
+        assert_eq!(token.literal, "x");
+        let trailing: Vec<_> = token.trailing_trivia.iter()
+            .map(|trivia| (trivia.kind, trivia.text.as_ref()))
+            .collect();
+        assert_eq!(trailing, vec![
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::LineComment, "// x"),
+        ]);
+
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::Assign);
+        let leading: Vec<_> = token.leading_trivia.iter()
+            .map(|trivia| (trivia.kind, trivia.text.as_ref()))
+            .collect();
+        assert_eq!(leading, vec![
+            (TriviaKind::Newline, "\r\n"),
+            (TriviaKind::BlockComment, "/* y */"),
+            (TriviaKind::Whitespace, " "),
+        ]);
+
+        assert_eq!(lexer.next_token().token_type, TokenType::Int);
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::Semicolon);
+        assert!(token.trailing_trivia.is_empty());
+
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::EOF);
+        assert_eq!(token.leading_trivia[0].kind, TriviaKind::Newline);
+    }
+
+    #[test]
+    fn test_trivia_is_dropped_by_default() {
+        let mut lexer = Lexer::new(" // a\nlet ");
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::Let);
+        assert!(token.leading_trivia.is_empty());
+        assert!(token.trailing_trivia.is_empty());
+    }
+
+    #[test]
+    fn test_iterator_yields_eof_once() {
+        let mut lexer = Lexer::new("a + 1");
+        let types: Vec<TokenType> = lexer.by_ref().map(|token| token.token_type).collect();
+        assert_eq!(types, vec![TokenType::Ident, TokenType::Plus, TokenType::Int, TokenType::EOF]);
+        assert!(lexer.next().is_none());
+        assert!(lexer.next().is_none());
+
+        let mut lexer = Lexer::new("");
+        assert_eq!(lexer.next().map(|token| token.token_type), Some(TokenType::EOF));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_peek_nth() {
+        let mut lexer = Lexer::new("let x = 5;");
+        assert_eq!(lexer.peek_nth(2).token_type, TokenType::Assign);
+        assert_eq!(lexer.peek().token_type, TokenType::Let);
+        assert_eq!(lexer.peek_nth(10).token_type, TokenType::EOF);
+
+        assert_eq!(lexer.next_token().token_type, TokenType::Let);
+        assert_eq!(lexer.peek().literal, "x");
+        let literals: Vec<_> = lexer.map(|token| token.literal).collect();
+        assert_eq!(literals, vec!["x", "=", "5", ";", ""]);
+    }
+
+    #[test]
+    fn test_peekable() {
+        let mut tokens = Lexer::new("f(1)").peekable();
+        assert_eq!(tokens.next().unwrap().token_type, TokenType::Ident);
+        assert!(tokens.next_if(|token| token.token_type == TokenType::LParen).is_some());
+        assert!(tokens.next_if(|token| token.token_type == TokenType::RParen).is_none());
+        assert_eq!(tokens.count(), 3);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("x == 10").unwrap();
+        let types: Vec<TokenType> = tokens.iter().map(|token| token.token_type).collect();
+        assert_eq!(types, vec![TokenType::Ident, TokenType::EQ, TokenType::Int, TokenType::EOF]);
+
+        assert_eq!(tokenize("1 @ 2 #").unwrap_err(), LexError::IllegalCharacter {
+            ch: '@',
+            span: Span::new(Position::new(2, 1, 3), Position::new(3, 1, 4)),
+        });
+    }
+
+    #[test]
+    fn test_literals_borrow_from_input() {
+        let input = String::from(r#"let name = "plain"; "esc\n""#);
+        let mut lexer = Lexer::new(&input);
+        let tokens: Vec<_> = lexer.by_ref().collect();
+
+        assert!(matches!(tokens[1].literal, Cow::Borrowed("name")));
+        assert!(matches!(tokens[3].literal, Cow::Borrowed("plain")));
+        assert!(matches!(&tokens[5].literal, Cow::Owned(value) if value == "esc\n"));
+
+        let owned: Vec<_> = tokens.into_iter().map(|token| token.into_owned()).collect();
+        drop(input);
+        assert_eq!(owned[1].literal, "name");
+        assert_eq!(owned[1].span, Span::new(Position::new(4, 1, 5), Position::new(8, 1, 9)));
+    }
+}
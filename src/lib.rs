@@ -0,0 +1,12 @@
+pub mod token;
+pub mod lexer;
+pub mod ast;
+pub mod parser;
+pub mod error;
+pub mod diagnostic;
+pub mod cst;
+pub mod stream;
+pub mod object;
+pub mod environment;
+pub mod evaluator;
+pub mod resolver;
@@ -1,30 +1,48 @@
+use std::cell::RefCell;
 use std::io;
 use std::io::{stdout, Write};
+use std::rc::Rc;
 
-
-mod token;
-mod lexer;
-mod ast;
-mod parser;
+use rustymonkey::diagnostic::render;
+use rustymonkey::environment::Environment;
+use rustymonkey::evaluator::eval_program;
+use rustymonkey::lexer::Lexer;
+use rustymonkey::parser::Parser;
+use rustymonkey::resolver::Resolver;
 
 const PROMPT: &str = ">> ";
 
 fn main() {
     println!("Hello {}! This is the Monkey programming language!", whoami::username());
     println!("Feel free to type in commands");
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let mut resolver = Resolver::new();
     loop {
         print!("{}", PROMPT);
         stdout().flush().unwrap();
 
         let mut buffer = String::new();
-        io::stdin().read_line(&mut buffer).unwrap();
-        let mut lexer = lexer::Lexer::new(buffer);
-        loop {
-            let token = lexer.next_token();
-            if token.token_type == token::TokenType::EOF {
-                break;
+        if io::stdin().read_line(&mut buffer).unwrap() == 0 {
+            break;
+        }
+        let lexer = Lexer::new(&buffer);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        if !parser.errors().is_empty() {
+            for error in parser.errors() {
+                print!("{}", render(&buffer, error));
             }
-            println!("{:?}", token);
+            continue;
         }
+        let errors = resolver.resolve(&program);
+        if !errors.is_empty() {
+            for error in &errors {
+                print!("{}", render(&buffer, error));
+            }
+            continue;
+        }
+
+        let evaluated = eval_program(&program, &environment);
+        println!("{}", evaluated);
     }
 }
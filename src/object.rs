@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter, Result};
+use std::rc::Rc;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use crate::ast::{BlockStatement, Identifier};
+use crate::environment::Environment;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Integer(i64),
+    /// An integer outside the range of an `i64`. Build these with `Object::from`, which keeps
+    /// smaller values as `Integer`.
+    BigInteger(BigInt),
+    Float(f64),
+    Boolean(bool),
+    String(String),
+    Array(Vec<Object>),
+    Hash(Hash),
+    Null,
+    ReturnValue(Box<Object>),
+    /// Unwinds a loop body after `break`, like `ReturnValue` does for a function body.
+    Break,
+    /// Unwinds a loop body after `continue`.
+    Continue,
+    Error(String),
+    Function(Rc<Function>),
+}
+
+pub const TRUE: Object = Object::Boolean(true);
+pub const FALSE: Object = Object::Boolean(false);
+pub const NULL: Object = Object::Null;
+
+impl Object {
+    pub fn object_type(&self) -> &'static str {
+        match self {
+            Object::Integer(_) | Object::BigInteger(_) => "INTEGER",
+            Object::Float(_) => "FLOAT",
+            Object::Boolean(_) => "BOOLEAN",
+            Object::String(_) => "STRING",
+            Object::Array(_) => "ARRAY",
+            Object::Hash(_) => "HASH",
+            Object::Null => "NULL",
+            Object::ReturnValue(_) => "RETURN_VALUE",
+            Object::Break => "BREAK",
+            Object::Continue => "CONTINUE",
+            Object::Error(_) => "ERROR",
+            Object::Function(_) => "FUNCTION",
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Object::Error(_))
+    }
+
+    /// Returns true for the objects that stop evaluation and are handed up to the enclosing
+    /// loop, function or program: errors and the results of `return`, `break` and `continue`.
+    pub fn is_unwinding(&self) -> bool {
+        matches!(self, Object::ReturnValue(_) | Object::Break | Object::Continue | Object::Error(_))
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Null | Object::Boolean(false))
+    }
+
+    /// Returns the key this object is stored under in a hash, or `None` if it cannot be a key.
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Object::Integer(value) => Some(HashKey::Integer(*value)),
+            Object::BigInteger(value) => Some(HashKey::BigInteger(value.clone())),
+            Object::Boolean(value) => Some(HashKey::Boolean(*value)),
+            Object::String(value) => Some(HashKey::String(value.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl From<BigInt> for Object {
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Object::Integer(value),
+            None => Object::BigInteger(value),
+        }
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Object::Integer(value) => write!(f, "{}", value),
+            Object::BigInteger(value) => write!(f, "{}", value),
+            // Debug formatting keeps the decimal point on whole numbers, so `3.0` is not shown as
+            // the integer `3`.
+            Object::Float(value) => write!(f, "{:?}", value),
+            Object::Boolean(value) => write!(f, "{}", value),
+            Object::String(value) => write!(f, "{}", value),
+            Object::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Object::Hash(hash) => write!(f, "{}", hash),
+            Object::Null => write!(f, "null"),
+            Object::ReturnValue(value) => write!(f, "{}", value),
+            Object::Break => write!(f, "break"),
+            Object::Continue => write!(f, "continue"),
+            Object::Error(message) => write!(f, "ERROR: {}", message),
+            Object::Function(function) => write!(f, "{}", function),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    Integer(i64),
+    BigInteger(BigInt),
+    Boolean(bool),
+    String(String),
+}
+
+/// A hash map that remembers the order its keys were first inserted in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hash {
+    pairs: Vec<(Object, Object)>,
+    index: HashMap<HashKey, usize>,
+}
+
+impl Hash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a pair, replacing the value in place if the key is already present.
+    pub fn insert(&mut self, key: HashKey, key_object: Object, value: Object) {
+        match self.index.get(&key) {
+            Some(&position) => self.pairs[position].1 = value,
+            None => {
+                self.index.insert(key, self.pairs.len());
+                self.pairs.push((key_object, value));
+            }
+        }
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Object> {
+        self.index.get(key).map(|&position| &self.pairs[position].1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns the pairs in insertion order.
+    pub fn pairs(&self) -> &[(Object, Object)] {
+        &self.pairs
+    }
+}
+
+impl Display for Hash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pairs: Vec<String> = self.pairs.iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect();
+        write!(f, "{{{}}}", pairs.join(", "))
+    }
+}
+
+/// A function value together with the environment it closes over. The parameters and body are
+/// copied out of the source text, since a function can outlive the program that defined it, and
+/// are shared with every other function value made from the same literal.
+pub struct Function {
+    pub parameters: Rc<[Identifier<'static>]>,
+    pub body: Rc<BlockStatement<'static>>,
+    pub environment: Rc<RefCell<Environment>>,
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Function {
+    // The captured environment usually refers back to this function, so it is left out.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Function({})", self)
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.to_string()).collect();
+        write!(f, "fn({}) {}", parameters.join(", "), self.body)
+    }
+}
@@ -1,48 +1,87 @@
 use crate::ast::{Identifier, LetStatement, Program, Statement, ReturnStatement, Expression,
-                 ExpressionStatement, IntegerLiteral, PrefixExpression, InfixExpression};
-use crate::lexer::Lexer;
-use crate::token::{Token, TokenType};
+                 ExpressionStatement, IntegerLiteral, PrefixExpression, InfixExpression,
+                 BooleanLiteral, BlockStatement, IfExpression, FunctionLiteral, CallExpression,
+                 StringLiteral, FloatLiteral, BigIntegerLiteral, LogicalExpression,
+                 ArrayLiteral, IndexExpression, HashLiteral, WhileStatement, ForStatement,
+                 BreakStatement, ContinueStatement, AssignExpression, ConstStatement, Node};
+use std::cell::OnceCell;
+use num_bigint::BigInt;
+use crate::error::{LexError, ParseError};
+use crate::lexer::{Lexer, TokenSource};
+use crate::token::{integer_radix, Token, TokenType};
 
 #[derive(PartialEq, PartialOrd)]
 enum Precedence {
     Lowest,
+    Assign,
+    LogicalOr,
+    LogicalAnd,
     Equals,
     LessGreater,
+    // Bitwise operators bind tighter than comparisons, as in Rust.
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     Sum,
     Product,
     Prefix,
     Call,
+    Index,
 }
 
 fn precedence_for_token_type(token_type: &TokenType) -> Precedence {
     match token_type {
+        TokenType::Assign => Precedence::Assign,
+        TokenType::PlusAssign => Precedence::Assign,
+        TokenType::MinusAssign => Precedence::Assign,
+        TokenType::AsteriskAssign => Precedence::Assign,
+        TokenType::SlashAssign => Precedence::Assign,
+        TokenType::Or => Precedence::LogicalOr,
+        TokenType::And => Precedence::LogicalAnd,
         TokenType::EQ => Precedence::Equals,
         TokenType::NotEQ => Precedence::Equals,
         TokenType::LT => Precedence::LessGreater,
         TokenType::GT => Precedence::LessGreater,
+        TokenType::LTEQ => Precedence::LessGreater,
+        TokenType::GTEQ => Precedence::LessGreater,
+        TokenType::Pipe => Precedence::BitOr,
+        TokenType::Caret => Precedence::BitXor,
+        TokenType::Ampersand => Precedence::BitAnd,
+        TokenType::ShiftLeft => Precedence::Shift,
+        TokenType::ShiftRight => Precedence::Shift,
         TokenType::Plus => Precedence::Sum,
         TokenType::Minus => Precedence::Sum,
         TokenType::Slash => Precedence::Product,
         TokenType::Asterisk => Precedence::Product,
+        TokenType::Percent => Precedence::Product,
         TokenType::LParen => Precedence::Call,
+        TokenType::LBracket => Precedence::Index,
         _ => Precedence::Lowest,
     }
 }
 
-struct Parser {
-    lexer: Lexer,
-    current_token: Token,
-    peek_token: Token,
-    errors: Vec<String>,
+pub struct Parser<'src, S = Lexer<'src>> {
+    lexer: S,
+    current_token: Token<'src>,
+    peek_token: Token<'src>,
+    current_docs: Vec<String>,
+    peek_docs: Vec<String>,
+    errors: Vec<ParseError>,
+    /// How many loops enclose the current token within the current function body.
+    loop_depth: usize,
 }
 
-impl Parser {
-    pub fn new(lexer: Lexer) -> Self {
+impl<'src, S: TokenSource<'src>> Parser<'src, S> {
+    pub fn new(lexer: S) -> Self {
         let mut parser = Self {
             lexer,
             current_token: Token::new(TokenType::EOF, String::from("")),
             peek_token: Token::new(TokenType::EOF, String::from("")),
+            current_docs: Vec::new(),
+            peek_docs: Vec::new(),
             errors: Vec::new(),
+            loop_depth: 0,
         };
         parser.next_token();
         parser.next_token();
@@ -50,8 +89,20 @@ impl Parser {
     }
 
     fn next_token(&mut self) {
-        self.current_token = self.peek_token.clone();
-        self.peek_token = self.lexer.next_token();
+        self.current_token = std::mem::replace(&mut self.peek_token, self.lexer.next_token());
+        self.current_docs = std::mem::take(&mut self.peek_docs);
+        while self.peek_token.token_type == TokenType::DocComment {
+            self.peek_docs.push(self.peek_token.literal.to_string());
+            self.peek_token = self.lexer.next_token();
+        }
+        self.errors.extend(self.lexer.take_errors().into_iter().map(ParseError::Lex));
+    }
+
+    /// Copies the current token for the AST, leaving out its trivia. The literal still borrows
+    /// from the source text.
+    fn node_token(&self) -> Token<'src> {
+        let token = &self.current_token;
+        Token::with_span(token.token_type, token.literal.clone(), token.span)
     }
 
     fn current_token_is(&self, token_type: TokenType) -> bool {
@@ -65,17 +116,21 @@ impl Parser {
     fn expect_peek(&mut self, token_type: TokenType) -> bool {
         if self.peek_token_is(token_type) {
             self.next_token();
-            return true;
+            true
         } else {
             self.peek_error(token_type);
-            return false;
+            false
         }
     }
 
     fn peek_error(&mut self, token_type: TokenType) {
-        let error = format!("expected next token to be {:?}, got {:?} instead",
-                            token_type.as_str(),
-                            self.peek_token.token_type.as_str());
+        let found = self.peek_token.token_type;
+        let span = self.peek_token.span;
+        let error = if found == TokenType::EOF {
+            ParseError::Unterminated { expected: token_type, found, span }
+        } else {
+            ParseError::UnexpectedToken { expected: token_type, found, span }
+        };
         self.errors.push(error);
     }
 
@@ -87,101 +142,232 @@ impl Parser {
         precedence_for_token_type(&self.current_token.token_type)
     }
 
-    pub fn parse_program(&mut self) -> Program {
+    pub fn parse_program(&mut self) -> Program<'src> {
         let mut program = Program::new();
 
         while self.current_token.token_type != TokenType::EOF {
-            match self.parse_statement() {
-                Some(statement) => program.statements.push(statement),
-                None => {}
+            if let Some(statement) = self.parse_statement() {
+                program.statements.push(statement);
             }
             self.next_token();
         }
-        return program;
+        program
     }
 
-    pub fn errors(&self) -> Vec<String> {
-        self.errors.clone()
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
     }
 
-    fn parse_statement(&mut self) -> Option<Box<dyn Statement>> {
+    fn parse_statement(&mut self) -> Option<Statement<'src>> {
         match self.current_token.token_type {
             TokenType::Let => self.parse_let_statement(),
+            TokenType::Const => self.parse_const_statement(),
             TokenType::Return => self.parse_return_statement(),
+            TokenType::While => self.parse_while_statement(),
+            TokenType::For => self.parse_for_statement(),
+            TokenType::Break | TokenType::Continue => self.parse_loop_control_statement(),
             _ => self.parse_expression_statement(),
         }
     }
 
-    fn parse_let_statement(&mut self) -> Option<Box<dyn Statement>> {
-        let token = self.current_token.clone();
+    fn parse_let_statement(&mut self) -> Option<Statement<'src>> {
+        let token = self.node_token();
+        let doc = self.current_doc();
+        let (name, value) = self.parse_binding()?;
+        Some(Statement::Let(LetStatement {
+            token,
+            name,
+            value,
+            doc,
+        }))
+    }
+
+    fn parse_const_statement(&mut self) -> Option<Statement<'src>> {
+        let token = self.node_token();
+        let doc = self.current_doc();
+        let (name, value) = self.parse_binding()?;
+        Some(Statement::Const(ConstStatement {
+            token,
+            name,
+            value,
+            doc,
+        }))
+    }
+
+    fn current_doc(&self) -> Option<String> {
+        if self.current_docs.is_empty() {
+            None
+        } else {
+            Some(self.current_docs.join("\n"))
+        }
+    }
+
+    /// Parses the `name = value;` that follows `let` or `const`.
+    fn parse_binding(&mut self) -> Option<(Identifier<'src>, Expression<'src>)> {
         if !self.expect_peek(TokenType::Ident) {
             return None;
         }
 
         let name = Identifier {
-            token: self.current_token.clone(),
+            token: self.node_token(),
             value: self.current_token.literal.clone()
         };
         if !self.expect_peek(TokenType::Assign) {
             return None;
         }
 
-        // TODO: Skipping expressions until we encounter a semicolon
-        while !self.current_token_is(TokenType::Semicolon) {
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Some((name, value))
+    }
+
+    fn parse_return_statement(&mut self) -> Option<Statement<'src>> {
+        let token = self.node_token();
+
+        // A closing brace ends a bare return too, but is left for the enclosing block.
+        if self.peek_token_is(TokenType::RBrace) {
+            return Some(Statement::Return(ReturnStatement { token, value: None }));
+        }
+        if self.peek_token_is(TokenType::Semicolon) || self.peek_token_is(TokenType::EOF) {
+            self.next_token();
+            return Some(Statement::Return(ReturnStatement {
+                token,
+                value: None,
+            }));
+        }
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        if self.peek_token_is(TokenType::Semicolon) {
             self.next_token();
         }
 
-        Some(Box::new(LetStatement {
+        Some(Statement::Return(ReturnStatement {
             token,
-            name,
-            value: Box::new(Identifier {
-                token: Token::new(TokenType::Ident, String::from("")),
-                value: String::from(""),
-            }),
+            value: Some(value),
         }))
     }
 
-    fn parse_return_statement(&mut self) -> Option<Box<dyn Statement>> {
-        let token = self.current_token.clone();
+    fn parse_while_statement(&mut self) -> Option<Statement<'src>> {
+        let token = self.node_token();
+        // As in `for`, no parentheses are needed.
         self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::LBrace) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Some(Statement::While(WhileStatement {
+            token,
+            condition,
+            body,
+        }))
+    }
 
-        // TODO: Skipping expressions until we encounter a semicolon
-        while !self.current_token_is(TokenType::Semicolon) {
+    fn parse_for_statement(&mut self) -> Option<Statement<'src>> {
+        let token = self.node_token();
+        if !self.expect_peek(TokenType::Ident) {
+            return None;
+        }
+        let variable = Identifier {
+            token: self.node_token(),
+            value: self.current_token.literal.clone(),
+        };
+        if !self.expect_peek(TokenType::In) {
+            return None;
+        }
+        self.next_token();
+        let iterable = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::LBrace) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+        if self.peek_token_is(TokenType::Semicolon) {
             self.next_token();
         }
 
-        Some(Box::new(ReturnStatement {
+        Some(Statement::For(ForStatement {
             token,
-            value: Some(Box::new(Identifier {
-                token: Token::new(TokenType::Ident, String::from("")),
-                value: String::from(""),
-            })),
+            variable,
+            iterable,
+            body,
         }))
     }
 
-    fn parse_expression_statement(&mut self) -> Option<Box<dyn Statement>> {
-        let token = self.current_token.clone();
+    fn parse_loop_body(&mut self) -> Option<BlockStatement<'src>> {
+        self.loop_depth += 1;
+        let body = self.parse_block_statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn parse_loop_control_statement(&mut self) -> Option<Statement<'src>> {
+        let token = self.node_token();
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
+        }
+        if self.loop_depth == 0 {
+            self.errors.push(ParseError::OutsideLoop {
+                keyword: token.literal.to_string(),
+                span: token.span,
+            });
+            return None;
+        }
+
+        Some(match token.token_type {
+            TokenType::Break => Statement::Break(BreakStatement { token }),
+            _ => Statement::Continue(ContinueStatement { token }),
+        })
+    }
+
+    fn parse_expression_statement(&mut self) -> Option<Statement<'src>> {
+        let token = self.node_token();
         let expression = self.parse_expression(Precedence::Lowest);
 
         if self.peek_token_is(TokenType::Semicolon) {
             self.next_token();
         }
 
-        Some(Box::new(ExpressionStatement {
+        Some(Statement::Expression(ExpressionStatement {
             token,
             expression,
         }))
     }
 
-    fn parse_expression(&mut self, precedence: Precedence) -> Option<Box<dyn Expression>> {
+    fn parse_expression(&mut self, precedence: Precedence) -> Option<Expression<'src>> {
         let mut left = match self.current_token.token_type {
             TokenType::Ident => self.parse_identifier(),
             TokenType::Int => self.parser_integer_literal(),
-            TokenType::Bang | TokenType::Minus => self.parse_prefix_expression(),
+            TokenType::Float => self.parse_float_literal(),
+            TokenType::String => self.parse_string_literal(),
+            TokenType::True | TokenType::False => self.parse_boolean_literal(),
+            TokenType::LParen => self.parse_grouped_expression(),
+            TokenType::LBracket => self.parse_array_literal(),
+            // Blocks only follow `if`, `else` and `fn`, so a brace here always opens a hash.
+            TokenType::LBrace => self.parse_hash_literal(),
+            TokenType::If => self.parse_if_expression(),
+            TokenType::Function => self.parse_function_literal(),
+            TokenType::Bang | TokenType::Minus | TokenType::Tilde => {
+                self.parse_prefix_expression()
+            }
+            // The lexer has already reported why the token is illegal.
+            TokenType::Illegal => return None,
             _ => {
-                self.errors.push(
-                    format!("no prefix parse function for {:?} found",
-                            self.current_token.token_type));
+                self.errors.push(ParseError::NoPrefixParseFunction {
+                    found: self.current_token.token_type,
+                    span: self.current_token.span,
+                });
                 return None;
             }
         };
@@ -189,9 +375,29 @@ impl Parser {
         while !self.peek_token_is(TokenType::Semicolon) && precedence < self.peek_precedence() {
             left = match self.peek_token.token_type {
                 TokenType::Plus | TokenType::Minus | TokenType::Slash | TokenType::Asterisk |
-                TokenType::EQ | TokenType::NotEQ | TokenType::LT | TokenType::GT => {
+                TokenType::Percent | TokenType::EQ | TokenType::NotEQ | TokenType::LT |
+                TokenType::GT | TokenType::LTEQ | TokenType::GTEQ | TokenType::Ampersand |
+                TokenType::Pipe | TokenType::Caret | TokenType::ShiftLeft |
+                TokenType::ShiftRight => {
+                    self.next_token();
+                    self.parse_infix_expression(left?)
+                }
+                TokenType::And | TokenType::Or => {
                     self.next_token();
-                    self.parse_infix_expression(left.unwrap())
+                    self.parse_logical_expression(left?)
+                }
+                TokenType::Assign | TokenType::PlusAssign | TokenType::MinusAssign |
+                TokenType::AsteriskAssign | TokenType::SlashAssign => {
+                    self.next_token();
+                    self.parse_assign_expression(left?)
+                }
+                TokenType::LParen => {
+                    self.next_token();
+                    self.parse_call_expression(left?)
+                }
+                TokenType::LBracket => {
+                    self.next_token();
+                    self.parse_index_expression(left?)
                 }
                 _ => return left,
             };
@@ -199,67 +405,374 @@ impl Parser {
         left
     }
 
-    fn parse_identifier(&mut self) -> Option<Box<dyn Expression>> {
-        Some(Box::new(Identifier {
-            token: self.current_token.clone(),
+    fn parse_identifier(&mut self) -> Option<Expression<'src>> {
+        Some(Expression::Identifier(Identifier {
+            token: self.node_token(),
             value: self.current_token.literal.clone(),
         }))
     }
 
-    fn parser_integer_literal(&mut self) -> Option<Box<dyn Expression>> {
-        let token = self.current_token.clone();
-        match self.current_token.literal.parse::<i64>() {
-            Ok(value) => Some(Box::new(IntegerLiteral {
+    fn parser_integer_literal(&mut self) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let (radix, digits) = integer_radix(&self.current_token.literal);
+        let digits = digits.replace('_', "");
+        match i64::from_str_radix(&digits, radix) {
+            Ok(value) => Some(Expression::IntegerLiteral(IntegerLiteral {
                 token,
                 value,
             })),
-            Err(_) => {
-                self.errors.push(
-                    format!("could not parse {:?} as integer",
-                            self.current_token.literal));
-                None
+            // Too big for an i64, so the literal is kept exactly.
+            Err(_) => match BigInt::parse_bytes(digits.as_bytes(), radix) {
+                Some(value) => Some(Expression::BigIntegerLiteral(BigIntegerLiteral {
+                    token,
+                    value,
+                })),
+                // The lexer only produces valid digits, but other token sources may not.
+                None => {
+                    self.errors.push(ParseError::Lex(LexError::MalformedNumber {
+                        literal: self.current_token.literal.to_string(),
+                        span: self.current_token.span,
+                    }));
+                    None
+                }
+            },
+        }
+    }
+
+    fn parse_float_literal(&mut self) -> Option<Expression<'src>> {
+        // The lexer only produces well-formed float literals, which always parse.
+        let value = self.current_token.literal.replace('_', "").parse::<f64>().ok()?;
+        Some(Expression::FloatLiteral(FloatLiteral {
+            token: self.node_token(),
+            value,
+        }))
+    }
+
+    fn parse_string_literal(&mut self) -> Option<Expression<'src>> {
+        Some(Expression::StringLiteral(StringLiteral {
+            token: self.node_token(),
+            value: self.current_token.literal.clone(),
+        }))
+    }
+
+    fn parse_boolean_literal(&mut self) -> Option<Expression<'src>> {
+        Some(Expression::BooleanLiteral(BooleanLiteral {
+            token: self.node_token(),
+            value: self.current_token.token_type == TokenType::True,
+        }))
+    }
+
+    fn parse_grouped_expression(&mut self) -> Option<Expression<'src>> {
+        self.next_token();
+        let expression = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::RParen) {
+            return None;
+        }
+        Some(expression)
+    }
+
+    fn parse_if_expression(&mut self) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        if !self.expect_peek(TokenType::LParen) {
+            return None;
+        }
+        self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::RParen) {
+            return None;
+        }
+        if !self.expect_peek(TokenType::LBrace) {
+            return None;
+        }
+        let consequence = self.parse_block_statement()?;
+
+        let mut alternative = None;
+        if self.peek_token_is(TokenType::Else) {
+            self.next_token();
+            if self.peek_token_is(TokenType::If) {
+                self.next_token();
+                let if_token = self.node_token();
+                let else_if = self.parse_if_expression()?;
+                alternative = Some(BlockStatement {
+                    token: if_token.clone(),
+                    closing: else_if.span(),
+                    statements: vec![Statement::Expression(ExpressionStatement {
+                        token: if_token,
+                        expression: Some(else_if),
+                    })],
+                });
+            } else {
+                if !self.expect_peek(TokenType::LBrace) {
+                    return None;
+                }
+                alternative = Some(self.parse_block_statement()?);
             }
         }
+
+        Some(Expression::If(IfExpression {
+            token,
+            condition: Box::new(condition),
+            consequence,
+            alternative,
+        }))
     }
 
-    fn parse_prefix_expression(&mut self) -> Option<Box<dyn Expression>> {
-        let token = self.current_token.clone();
-        let operator = self.current_token.literal.clone();
+    fn parse_block_statement(&mut self) -> Option<BlockStatement<'src>> {
+        let token = self.node_token();
+        let mut statements = Vec::new();
         self.next_token();
-        let right = self.parse_expression(Precedence::Prefix).unwrap();
-        Some(Box::new(PrefixExpression {
+
+        while !self.current_token_is(TokenType::RBrace) {
+            if self.current_token_is(TokenType::EOF) {
+                self.errors.push(ParseError::Unterminated {
+                    expected: TokenType::RBrace,
+                    found: TokenType::EOF,
+                    span: self.current_token.span,
+                });
+                return None;
+            }
+            if let Some(statement) = self.parse_statement() {
+                statements.push(statement);
+            }
+            self.next_token();
+        }
+
+        Some(BlockStatement {
+            token,
+            statements,
+            closing: self.current_token.span,
+        })
+    }
+
+    fn parse_function_literal(&mut self) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        if !self.expect_peek(TokenType::LParen) {
+            return None;
+        }
+        let parameters = self.parse_function_parameters()?;
+        if !self.expect_peek(TokenType::LBrace) {
+            return None;
+        }
+        // A loop around the function literal does not make `break` valid inside its body.
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let body = self.parse_block_statement();
+        self.loop_depth = loop_depth;
+        let body = body?;
+
+        Some(Expression::FunctionLiteral(FunctionLiteral {
+            token,
+            parameters,
+            body,
+            shared: OnceCell::new(),
+        }))
+    }
+
+    fn parse_function_parameters(&mut self) -> Option<Vec<Identifier<'src>>> {
+        let mut identifiers = Vec::new();
+        if self.peek_token_is(TokenType::RParen) {
+            self.next_token();
+            return Some(identifiers);
+        }
+
+        loop {
+            if !self.expect_peek(TokenType::Ident) {
+                return None;
+            }
+            identifiers.push(Identifier {
+                token: self.node_token(),
+                value: self.current_token.literal.clone(),
+            });
+            if !self.peek_token_is(TokenType::Comma) {
+                break;
+            }
+            self.next_token();
+        }
+
+        if !self.expect_peek(TokenType::RParen) {
+            return None;
+        }
+        Some(identifiers)
+    }
+
+    fn parse_call_expression(&mut self, function: Expression<'src>) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let arguments = self.parse_expression_list(TokenType::RParen)?;
+        Some(Expression::Call(CallExpression {
+            token,
+            function: Box::new(function),
+            arguments,
+            closing: self.current_token.span,
+        }))
+    }
+
+    fn parse_array_literal(&mut self) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let elements = self.parse_expression_list(TokenType::RBracket)?;
+        Some(Expression::ArrayLiteral(ArrayLiteral {
+            token,
+            elements,
+            closing: self.current_token.span,
+        }))
+    }
+
+    fn parse_hash_literal(&mut self) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let mut pairs = Vec::new();
+        while !self.peek_token_is(TokenType::RBrace) {
+            // Input that ends after `{` or a trailing comma is missing the brace, not a key.
+            if self.peek_token_is(TokenType::EOF) {
+                self.peek_error(TokenType::RBrace);
+                return None;
+            }
+            self.next_token();
+            let key = self.parse_expression(Precedence::Lowest)?;
+            if !self.expect_peek(TokenType::Colon) {
+                return None;
+            }
+            self.next_token();
+            let value = self.parse_expression(Precedence::Lowest)?;
+            pairs.push((key, value));
+
+            if self.peek_token_is(TokenType::RBrace) {
+                break;
+            }
+            // Running out of input is reported against the closing brace rather than the comma.
+            let separator = match self.peek_token.token_type {
+                TokenType::EOF => TokenType::RBrace,
+                _ => TokenType::Comma,
+            };
+            if !self.expect_peek(separator) {
+                return None;
+            }
+        }
+        if !self.expect_peek(TokenType::RBrace) {
+            return None;
+        }
+        Some(Expression::HashLiteral(HashLiteral {
+            token,
+            pairs,
+            closing: self.current_token.span,
+        }))
+    }
+
+    fn parse_index_expression(&mut self, left: Expression<'src>) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        self.next_token();
+        let index = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::RBracket) {
+            return None;
+        }
+        Some(Expression::Index(IndexExpression {
+            token,
+            left: Box::new(left),
+            index: Box::new(index),
+            closing: self.current_token.span,
+        }))
+    }
+
+    /// Parses a comma separated list of expressions up to and including the `end` token.
+    fn parse_expression_list(&mut self, end: TokenType) -> Option<Vec<Expression<'src>>> {
+        let mut list = Vec::new();
+        if self.peek_token_is(end) {
+            self.next_token();
+            return Some(list);
+        }
+
+        self.next_token();
+        list.push(self.parse_expression(Precedence::Lowest)?);
+        while self.peek_token_is(TokenType::Comma) {
+            self.next_token();
+            self.next_token();
+            list.push(self.parse_expression(Precedence::Lowest)?);
+        }
+
+        if !self.expect_peek(end) {
+            return None;
+        }
+        Some(list)
+    }
+
+    fn parse_prefix_expression(&mut self) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let operator = self.current_token.token_type.as_str();
+        self.next_token();
+        let right = self.parse_expression(Precedence::Prefix)?;
+        Some(Expression::Prefix(PrefixExpression {
             token,
             operator,
-            right,
+            right: Box::new(right),
         }))
     }
 
-    fn parse_infix_expression(&mut self, left: Box<dyn Expression>) -> Option<Box<dyn Expression>> {
-        let token = self.current_token.clone();
-        let operator = self.current_token.literal.clone();
+    fn parse_infix_expression(&mut self, left: Expression<'src>) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let operator = self.current_token.token_type.as_str();
         let precedence = self.current_precedence();
         self.next_token();
-        let right = self.parse_expression(precedence).unwrap();
-        Some(Box::new(InfixExpression {
+        let right = self.parse_expression(precedence)?;
+        Some(Expression::Infix(InfixExpression {
             token,
             operator,
-            left,
-            right,
+            left: Box::new(left),
+            right: Box::new(right),
+        }))
+    }
+
+    fn parse_assign_expression(&mut self, target: Expression<'src>) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let operator = self.current_token.token_type.as_str();
+        if !Self::is_assignable(&target) {
+            self.errors.push(ParseError::InvalidAssignmentTarget {
+                target: target.to_string(),
+                span: target.span(),
+            });
+            return None;
+        }
+        self.next_token();
+        // Parsing the value at the lowest precedence makes `a = b = c` group as `a = (b = c)`.
+        let value = self.parse_expression(Precedence::Lowest)?;
+        Some(Expression::Assign(AssignExpression {
+            token,
+            target: Box::new(target),
+            operator,
+            value: Box::new(value),
+        }))
+    }
+
+    fn is_assignable(target: &Expression) -> bool {
+        match target {
+            Expression::Identifier(_) => true,
+            Expression::Index(index_expression) => Self::is_assignable(&index_expression.left),
+            _ => false,
+        }
+    }
+
+    fn parse_logical_expression(&mut self, left: Expression<'src>) -> Option<Expression<'src>> {
+        let token = self.node_token();
+        let operator = self.current_token.token_type.as_str();
+        let precedence = self.current_precedence();
+        self.next_token();
+        let right = self.parse_expression(precedence)?;
+        Some(Expression::Logical(LogicalExpression {
+            token,
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
         }))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ast::{ExpressionStatement, IntegerLiteral, LetStatement, Node, NodeType, Statement,
-                     to_concrete_expression, to_concrete_statement, PrefixExpression, Expression,
-                     InfixExpression};
-    use crate::lexer::Lexer;
+    use crate::ast::{Expression, Node, NodeType, Statement};
+    use crate::error::{LexError, ParseError};
+    use crate::lexer::{Lexer, TokenSource};
     use crate::parser::Parser;
+    use crate::token::{Position, Span, Token, TokenType};
 
     fn check_parser_errors(parser: &Parser) {
         let errors = parser.errors();
-        if errors.len() == 0 {
+        if errors.is_empty() {
             return;
         }
 
@@ -270,29 +783,33 @@ mod tests {
         panic!();
     }
 
-    fn test_let_statement(statement: &Box<dyn Statement>, name: &str) -> bool {
+    fn test_let_statement(statement: &Statement, name: &str) -> bool {
         assert_eq!(statement.node_type(), NodeType::LetStatement);
         assert_eq!(statement.token_literal(), String::from("let"));
 
-        let let_statement = to_concrete_statement::<LetStatement>(statement);
+        let Statement::Let(let_statement) = statement else {
+            panic!("expected LetStatement, got {:?}", statement);
+        };
         assert_eq!(let_statement.name.value, name);
         assert_eq!(let_statement.name.token_literal(), name);
-        return true;
+        true
     }
 
-    fn test_integer_literal(expression: &Box<dyn Expression>, value: i64) -> bool {
+    fn test_integer_literal(expression: &Expression, value: i64) -> bool {
         assert_eq!(expression.node_type(), NodeType::IntegerLiteral);
-        let integer_literal = to_concrete_expression::<IntegerLiteral>(expression);
+        let Expression::IntegerLiteral(integer_literal) = expression else {
+            panic!("expected IntegerLiteral, got {:?}", expression);
+        };
         assert_eq!(integer_literal.value, value);
         assert_eq!(integer_literal.token_literal(), value.to_string());
-        return true;
+        true
     }
 
     #[test]
     fn test_let_statements() {
-        let input = String::from("let x = 5;
+        let input = "let x = 5;
 let y = 10;
-let foobar = 838383;");
+let foobar = 838383;";
 
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
@@ -309,9 +826,9 @@ let foobar = 838383;");
 
     #[test]
     fn test_return_statements() {
-        let input = String::from("return 5;
+        let input = "return 5;
 return 10;
-return 993322;");
+return 993322;";
 
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
@@ -325,9 +842,180 @@ return 993322;");
         }
     }
 
+    fn parse_single_statement(input: &str) -> Statement<'_> {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let mut program = parser.parse_program();
+        check_parser_errors(&parser);
+        assert_eq!(program.statements.len(), 1);
+        program.statements.remove(0)
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let statement = parse_single_statement("while (x < 10) { let x = x + 1; }");
+        let Statement::While(while_statement) = &statement else {
+            panic!("expected WhileStatement, got {:?}", statement);
+        };
+        assert_eq!(while_statement.condition.to_string(), "(x < 10)");
+        assert_eq!(while_statement.body.statements.len(), 1);
+        assert_eq!(statement.to_string(), "while (x < 10) { let x = (x + 1); }");
+
+        let statement = parse_single_statement("while x < 10 { x }");
+        assert_eq!(statement.to_string(), "while (x < 10) { x }");
+        let statement = parse_single_statement("while true { break; }");
+        assert_eq!(parse_single_statement(&statement.to_string()).to_string(),
+                   "while true { break; }");
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let statement = parse_single_statement("for item in [1, 2][0] { item }");
+        let Statement::For(for_statement) = &statement else {
+            panic!("expected ForStatement, got {:?}", statement);
+        };
+        assert_eq!(for_statement.variable.value, "item");
+        assert_eq!(for_statement.iterable.to_string(), "([1, 2][0])");
+        assert_eq!(for_statement.body.statements.len(), 1);
+        assert_eq!(statement.to_string(), "for item in ([1, 2][0]) { item }");
+    }
+
+    #[test]
+    fn test_loop_control_statements() {
+        let statement = parse_single_statement(
+            "while (true) { if (a) { break; } for x in y { continue } break }");
+        assert_eq!(statement.to_string(),
+                   "while true { if a { break; } for x in y { continue; } break; }");
+        let Statement::While(while_statement) = &statement else {
+            panic!("expected WhileStatement, got {:?}", statement);
+        };
+        assert_eq!(while_statement.body.statements[2].node_type(), NodeType::BreakStatement);
+    }
+
+    macro_rules! test_let_statement_value {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, identifier, expected) = $value;
+
+                let lexer = Lexer::new(input);
+                let mut parser = Parser::new(lexer);
+                let program = parser.parse_program();
+                check_parser_errors(&parser);
+                assert_eq!(program.statements.len(), 1);
+
+                let statement = &program.statements[0];
+                assert!(test_let_statement(statement, identifier));
+                let Statement::Let(let_statement) = statement else {
+                    panic!("expected LetStatement, got {:?}", statement);
+                };
+                assert_eq!(let_statement.value.to_string(), expected);
+            }
+        )*
+        }
+    }
+
+    test_let_statement_value! {
+        test_let_statement_value_1: ("let x = 5;", "x", "5")
+        test_let_statement_value_2: ("let y = -a * b;", "y", "((-a) * b)")
+        test_let_statement_value_3: ("let foobar = y", "foobar", "y")
+    }
+
+    #[test]
+    fn test_let_statement_doc_comments() {
+        let input = "/// The answer.
+/// Computed at length.
+let answer = 42;
+let plain = 1;
+/// Dropped, not followed by a let.
+answer;";
+
+        let lexer = Lexer::new(input).with_doc_comments();
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+        assert_eq!(program.statements.len(), 3);
+
+        let docs: Vec<Option<&str>> = program.statements.iter()
+            .filter_map(|statement| match statement {
+                Statement::Let(let_statement) => Some(let_statement.doc.as_deref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(docs, vec![Some("The answer.\nComputed at length."), None]);
+    }
+
+    #[test]
+    fn test_const_statement() {
+        let input = "/// The limit.
+const limit = 10 * 2;";
+
+        let lexer = Lexer::new(input).with_doc_comments();
+        let statement = Parser::new(lexer).parse_program().statements.remove(0);
+        let Statement::Const(const_statement) = &statement else {
+            panic!("expected ConstStatement, got {:?}", statement);
+        };
+        assert_eq!(const_statement.name.value, "limit");
+        assert_eq!(const_statement.value.to_string(), "(10 * 2)");
+        assert_eq!(const_statement.doc.as_deref(), Some("The limit."));
+        assert_eq!(statement.to_string(), "const limit = (10 * 2);");
+        assert_eq!(parse_errors("const = 1;")[0], ParseError::UnexpectedToken {
+            expected: TokenType::Ident,
+            found: TokenType::Assign,
+            span: Span::new(Position::new(6, 1, 7), Position::new(7, 1, 8)),
+        });
+    }
+
+    macro_rules! test_return_statement_value {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+
+                let lexer = Lexer::new(input);
+                let mut parser = Parser::new(lexer);
+                let program = parser.parse_program();
+                check_parser_errors(&parser);
+                assert_eq!(program.statements.len(), 1);
+
+                let statement = &program.statements[0];
+                assert_eq!(statement.node_type(), NodeType::ReturnStatement);
+                let Statement::Return(return_statement) = statement else {
+                    panic!("expected ReturnStatement, got {:?}", statement);
+                };
+                let value: Option<String> = expected.map(String::from);
+                assert_eq!(return_statement.value.as_ref().map(|value| value.to_string()), value);
+            }
+        )*
+        }
+    }
+
+    test_return_statement_value! {
+        test_return_statement_value_1: ("return 5;", Some("5"))
+        test_return_statement_value_2: ("return a + b * c;", Some("(a + (b * c))"))
+        test_return_statement_value_3: ("return foobar", Some("foobar"))
+        test_return_statement_value_4: ("return;", None::<&str>)
+        test_return_statement_value_5: ("return", None::<&str>)
+    }
+
+    #[test]
+    fn test_bare_return_before_closing_brace() {
+        let expression = parse_single_expression("fn() { return }");
+        let Expression::FunctionLiteral(function_literal) = &expression else {
+            panic!("expected FunctionLiteral, got {:?}", expression);
+        };
+        assert_eq!(function_literal.body.statements.len(), 1);
+        let Statement::Return(return_statement) = &function_literal.body.statements[0] else {
+            panic!("expected ReturnStatement, got {:?}", function_literal.body.statements[0]);
+        };
+        assert!(return_statement.value.is_none());
+    }
+
     #[test]
     fn test_identifier_expression() {
-        let input = String::from("foobar;");
+        let input = "foobar;";
 
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
@@ -337,14 +1025,17 @@ return 993322;");
 
         let statement = &program.statements[0];
         assert_eq!(statement.node_type(), NodeType::ExpressionStatement);
-        let expression = to_concrete_statement::<ExpressionStatement>(statement).expression.unwrap();
+        let Statement::Expression(expression_statement) = statement else {
+            panic!("expected ExpressionStatement, got {:?}", statement);
+        };
+        let expression = expression_statement.expression.as_ref().unwrap();
         assert_eq!(expression.node_type(), NodeType::Identifier);
         assert_eq!(expression.token_literal(), String::from("foobar"));
     }
 
     #[test]
     fn test_integer_literal_expression() {
-        let input = String::from("5;");
+        let input = "5;";
 
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
@@ -354,17 +1045,370 @@ return 993322;");
 
         let statement = &program.statements[0];
         assert_eq!(statement.node_type(), NodeType::ExpressionStatement);
-        let expression_statement = to_concrete_statement::<ExpressionStatement>(statement);
+        let Statement::Expression(expression_statement) = statement else {
+            panic!("expected ExpressionStatement, got {:?}", statement);
+        };
 
-        assert!(!expression_statement.expression.is_none());
-        let expression = expression_statement.expression.unwrap();
+        assert!(expression_statement.expression.is_some());
+        let expression = expression_statement.expression.as_ref().unwrap();
 
         assert_eq!(expression.node_type(), NodeType::IntegerLiteral);
-        let integer_literal = to_concrete_expression::<IntegerLiteral>(&expression);
+        let Expression::IntegerLiteral(integer_literal) = expression else {
+            panic!("expected IntegerLiteral, got {:?}", expression);
+        };
         assert_eq!(integer_literal.value, 5);
         assert_eq!(integer_literal.token_literal(), String::from("5"));
     }
 
+    macro_rules! test_integer_literal_radix {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+                let expression = parse_single_expression(input);
+                let Expression::IntegerLiteral(integer_literal) = &expression else {
+                    panic!("expected IntegerLiteral, got {:?}", expression);
+                };
+                assert_eq!(integer_literal.value, expected);
+                assert_eq!(integer_literal.to_string(), input);
+            }
+        )*
+        }
+    }
+
+    test_integer_literal_radix! {
+        test_integer_literal_radix_1: ("0xff", 255)
+        test_integer_literal_radix_2: ("0o17", 15)
+        test_integer_literal_radix_3: ("0b1010_0101", 165)
+        test_integer_literal_radix_4: ("1_000_000", 1_000_000)
+        test_integer_literal_radix_5: ("0x7fff_ffff_ffff_ffff", i64::MAX)
+    }
+
+    #[test]
+    fn test_big_integer_literal_expression() {
+        let expression = parse_single_expression("0x1_0000_0000_0000_0000;");
+
+        assert_eq!(expression.node_type(), NodeType::BigIntegerLiteral);
+        let Expression::BigIntegerLiteral(big_integer_literal) = expression else {
+            panic!("expected BigIntegerLiteral, got {:?}", expression);
+        };
+        assert_eq!(big_integer_literal.value.to_string(), "18446744073709551616");
+        assert_eq!(big_integer_literal.to_string(), "0x1_0000_0000_0000_0000");
+    }
+
+    #[test]
+    fn test_i64_overflow_literal() {
+        let statement = parse_single_statement("let mask = 0x8000_0000_0000_0000;");
+
+        let Statement::Let(let_statement) = statement else {
+            panic!("expected a let statement, got {:?}", statement);
+        };
+        let Expression::BigIntegerLiteral(big_integer_literal) = &let_statement.value else {
+            panic!("expected BigIntegerLiteral, got {:?}", let_statement.value);
+        };
+        assert_eq!(big_integer_literal.value.to_string(), "9223372036854775808");
+        assert_eq!(big_integer_literal.span(),
+                   Span::new(Position::new(11, 1, 12), Position::new(32, 1, 33)));
+    }
+
+    /// Hands the parser a fixed list of tokens, followed by `EOF`.
+    struct Tokens(std::vec::IntoIter<Token<'static>>);
+
+    impl TokenSource<'static> for Tokens {
+        fn next_token(&mut self) -> Token<'static> {
+            self.0.next().unwrap_or_else(|| Token::new(TokenType::EOF, ""))
+        }
+
+        fn take_errors(&mut self) -> Vec<LexError> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_invalid_integer_token_error() {
+        let span = Span::new(Position::new(0, 1, 1), Position::new(25, 1, 26));
+        let token = Token::with_span(TokenType::Int, "12345678901234567890_ab_c", span);
+        let mut parser = Parser::new(Tokens(vec![token].into_iter()));
+        parser.parse_program();
+
+        assert_eq!(parser.errors(), &[ParseError::Lex(LexError::MalformedNumber {
+            literal: String::from("12345678901234567890_ab_c"),
+            span,
+        })]);
+    }
+
+    #[test]
+    fn test_float_literal_expression() {
+        let expression = parse_single_expression("1.5e-3;");
+
+        assert_eq!(expression.node_type(), NodeType::FloatLiteral);
+        let Expression::FloatLiteral(float_literal) = expression else {
+            panic!("expected FloatLiteral, got {:?}", expression);
+        };
+        assert_eq!(float_literal.value, 0.0015);
+        assert_eq!(float_literal.to_string(), "1.5e-3");
+    }
+
+    macro_rules! test_boolean_expression {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected) = $value;
+
+                let lexer = Lexer::new(input);
+                let mut parser = Parser::new(lexer);
+                let program = parser.parse_program();
+                check_parser_errors(&parser);
+                assert_eq!(program.statements.len(), 1);
+
+                let statement = &program.statements[0];
+                let Statement::Expression(expression_statement) = statement else {
+                    panic!("expected ExpressionStatement, got {:?}", statement);
+                };
+                let expression = expression_statement.expression.as_ref().unwrap();
+
+                assert_eq!(expression.node_type(), NodeType::BooleanLiteral);
+                let Expression::BooleanLiteral(boolean_literal) = expression else {
+                    panic!("expected BooleanLiteral, got {:?}", expression);
+                };
+                assert_eq!(boolean_literal.value, expected);
+                assert_eq!(boolean_literal.token_literal(), expected.to_string());
+            }
+        )*
+        }
+    }
+
+    test_boolean_expression! {
+        test_boolean_expression_1: ("true;", true)
+        test_boolean_expression_2: ("false;", false)
+    }
+
+    fn test_identifier(expression: &Expression, value: &str) -> bool {
+        let Expression::Identifier(identifier) = expression else {
+            panic!("expected Identifier, got {:?}", expression);
+        };
+        assert_eq!(identifier.value, value);
+        assert_eq!(identifier.token_literal(), value);
+        true
+    }
+
+    fn test_infix_identifiers(expression: &Expression, left: &str, operator: &str,
+                              right: &str) -> bool {
+        let Expression::Infix(infix_expression) = expression else {
+            panic!("expected InfixExpression, got {:?}", expression);
+        };
+        assert!(test_identifier(&infix_expression.left, left));
+        assert_eq!(infix_expression.operator, operator);
+        assert!(test_identifier(&infix_expression.right, right));
+        true
+    }
+
+    fn parse_single_expression(input: &str) -> Expression<'_> {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+        assert_eq!(program.statements.len(), 1);
+
+        let statement = &program.statements[0];
+        let Statement::Expression(expression_statement) = statement else {
+            panic!("expected ExpressionStatement, got {:?}", statement);
+        };
+        expression_statement.expression.clone().unwrap()
+    }
+
+    #[test]
+    fn test_string_literal_expression() {
+        let expression = parse_single_expression(r#""hello\t\"world\"\u{1};""#);
+        let Expression::StringLiteral(string_literal) = &expression else {
+            panic!("expected StringLiteral, got {:?}", expression);
+        };
+        assert_eq!(string_literal.value, "hello\t\"world\"\u{1};");
+        assert_eq!(expression.to_string(), r#""hello\t\"world\"\u{1};""#);
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let expression = parse_single_expression("if (x < y) { x }");
+        let Expression::If(if_expression) = &expression else {
+            panic!("expected IfExpression, got {:?}", expression);
+        };
+        assert!(test_infix_identifiers(&if_expression.condition, "x", "<", "y"));
+        assert_eq!(if_expression.consequence.statements.len(), 1);
+        let Statement::Expression(consequence) = &if_expression.consequence.statements[0] else {
+            panic!("expected ExpressionStatement");
+        };
+        assert!(test_identifier(consequence.expression.as_ref().unwrap(), "x"));
+        assert!(if_expression.alternative.is_none());
+        assert_eq!(expression.to_string(), "if (x < y) { x }");
+    }
+
+    #[test]
+    fn test_if_else_expression() {
+        let expression = parse_single_expression("if (x < y) { x } else { y }");
+        let Expression::If(if_expression) = &expression else {
+            panic!("expected IfExpression, got {:?}", expression);
+        };
+        assert!(test_infix_identifiers(&if_expression.condition, "x", "<", "y"));
+        let alternative = if_expression.alternative.as_ref().unwrap();
+        assert_eq!(alternative.statements.len(), 1);
+        let Statement::Expression(alternative) = &alternative.statements[0] else {
+            panic!("expected ExpressionStatement");
+        };
+        assert!(test_identifier(alternative.expression.as_ref().unwrap(), "y"));
+        assert_eq!(expression.to_string(), "if (x < y) { x } else { y }");
+    }
+
+    #[test]
+    fn test_else_if_expression() {
+        let expression = parse_single_expression(
+            "if (a) { x } else if (b) { y; z } else if (c) { return 1; } else { w }");
+        let Expression::If(if_expression) = &expression else {
+            panic!("expected IfExpression, got {:?}", expression);
+        };
+        let alternative = if_expression.alternative.as_ref().unwrap();
+        assert_eq!(alternative.statements.len(), 1);
+        let Statement::Expression(nested) = &alternative.statements[0] else {
+            panic!("expected ExpressionStatement");
+        };
+        assert_eq!(nested.expression.as_ref().unwrap().node_type(), NodeType::IfExpression);
+        assert_eq!(expression.to_string(),
+                   "if a { x } else { if b { y z } else { if c { return 1; } else { w } } }");
+    }
+
+    #[test]
+    fn test_function_literal_parsing() {
+        let expression = parse_single_expression("fn(x, y) { x + y; }");
+        let Expression::FunctionLiteral(function_literal) = &expression else {
+            panic!("expected FunctionLiteral, got {:?}", expression);
+        };
+        assert_eq!(function_literal.parameters.len(), 2);
+        assert_eq!(function_literal.parameters[0].value, "x");
+        assert_eq!(function_literal.parameters[1].value, "y");
+        assert_eq!(function_literal.body.statements.len(), 1);
+        let Statement::Expression(body) = &function_literal.body.statements[0] else {
+            panic!("expected ExpressionStatement");
+        };
+        assert!(test_infix_identifiers(body.expression.as_ref().unwrap(), "x", "+", "y"));
+        assert_eq!(expression.to_string(), "fn(x, y) { (x + y) }");
+    }
+
+    macro_rules! test_function_parameter_parsing {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected): (&str, &[&str]) = $value;
+
+                let expression = parse_single_expression(input);
+                let Expression::FunctionLiteral(function_literal) = &expression else {
+                    panic!("expected FunctionLiteral, got {:?}", expression);
+                };
+                let parameters: Vec<&str> = function_literal.parameters.iter()
+                    .map(|parameter| parameter.value.as_ref())
+                    .collect();
+                assert_eq!(parameters, expected);
+            }
+        )*
+        }
+    }
+
+    test_function_parameter_parsing! {
+        test_function_parameter_parsing_1: ("fn() {};", &[])
+        test_function_parameter_parsing_2: ("fn(x) {};", &["x"])
+        test_function_parameter_parsing_3: ("fn(x, y, z) {};", &["x", "y", "z"])
+    }
+
+    #[test]
+    fn test_call_expression_parsing() {
+        let expression = parse_single_expression("add(1, 2 * 3, 4 + 5);");
+        let Expression::Call(call_expression) = &expression else {
+            panic!("expected CallExpression, got {:?}", expression);
+        };
+        assert!(test_identifier(&call_expression.function, "add"));
+        assert_eq!(call_expression.arguments.len(), 3);
+        assert!(test_integer_literal(&call_expression.arguments[0], 1));
+        assert_eq!(call_expression.arguments[1].to_string(), "(2 * 3)");
+        assert_eq!(call_expression.arguments[2].to_string(), "(4 + 5)");
+    }
+
+    #[test]
+    fn test_array_literal_parsing() {
+        let expression = parse_single_expression("[1, 2 * 2, 3 + 3]");
+        let Expression::ArrayLiteral(array_literal) = &expression else {
+            panic!("expected ArrayLiteral, got {:?}", expression);
+        };
+        assert_eq!(array_literal.elements.len(), 3);
+        assert!(test_integer_literal(&array_literal.elements[0], 1));
+        assert_eq!(array_literal.elements[1].to_string(), "(2 * 2)");
+        assert_eq!(array_literal.elements[2].to_string(), "(3 + 3)");
+        assert_eq!(parse_single_expression("[]").to_string(), "[]");
+    }
+
+    #[test]
+    fn test_index_expression_parsing() {
+        let expression = parse_single_expression("myArray[1 + 1]");
+        let Expression::Index(index_expression) = &expression else {
+            panic!("expected IndexExpression, got {:?}", expression);
+        };
+        assert!(test_identifier(&index_expression.left, "myArray"));
+        assert_eq!(index_expression.index.to_string(), "(1 + 1)");
+    }
+
+    macro_rules! test_hash_literal_parsing {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected): (&str, &[(&str, &str)]) = $value;
+
+                let expression = parse_single_expression(input);
+                let Expression::HashLiteral(hash_literal) = &expression else {
+                    panic!("expected HashLiteral, got {:?}", expression);
+                };
+                let pairs: Vec<(String, String)> = hash_literal.pairs.iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                let expected: Vec<(String, String)> = expected.iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                assert_eq!(pairs, expected);
+            }
+        )*
+        }
+    }
+
+    test_hash_literal_parsing! {
+        test_hash_literal_parsing_1: ("{}", &[])
+        test_hash_literal_parsing_2: (r#"{"one": 1, "two": 2, "three": 3}"#,
+            &[("\"one\"", "1"), ("\"two\"", "2"), ("\"three\"", "3")])
+        test_hash_literal_parsing_3: ("{true: 1, 2: false,}", &[("true", "1"), ("2", "false")])
+        test_hash_literal_parsing_4: ("{a + 1: b * 2, f(x): [y][0]}",
+            &[("(a + 1)", "(b * 2)"), ("f(x)", "([y][0])")])
+        test_hash_literal_parsing_5: ("{z: 1, a: 2, m: 3}", &[("z", "1"), ("a", "2"), ("m", "3")])
+        test_hash_literal_parsing_6: ("{{}: {1: 2}}", &[("{}", "{1: 2}")])
+    }
+
+    #[test]
+    fn test_hash_literal_display() {
+        let expression = parse_single_expression(r#"{"a": 1, b: [2]}"#);
+        assert_eq!(expression.to_string(), r#"{"a": 1, b: [2]}"#);
+    }
+
+    #[test]
+    fn test_assign_expression_parsing() {
+        let expression = parse_single_expression("a[0][k] += 1;");
+        let Expression::Assign(assign_expression) = &expression else {
+            panic!("expected AssignExpression, got {:?}", expression);
+        };
+        assert_eq!(assign_expression.target.node_type(), NodeType::IndexExpression);
+        assert_eq!(assign_expression.target.to_string(), "((a[0])[k])");
+        assert_eq!(assign_expression.operator, "+=");
+        assert!(test_integer_literal(&assign_expression.value, 1));
+    }
+
     macro_rules! test_prefix_expression {
         ($($name:ident: $value:expr)*) => {
         $(
@@ -372,7 +1416,7 @@ return 993322;");
             fn $name(){
                 let (input, operator, value) = $value;
 
-                let lexer = Lexer::new(String::from(input));
+                let lexer = Lexer::new(input);
                 let mut parser = Parser::new(lexer);
                 let program = parser.parse_program();
                 check_parser_errors(&parser);
@@ -380,13 +1424,17 @@ return 993322;");
 
                 let statement = &program.statements[0];
                 assert_eq!(statement.node_type(), NodeType::ExpressionStatement);
-                let expression_statement = to_concrete_statement::<ExpressionStatement>(statement);
+                let Statement::Expression(expression_statement) = statement else {
+                    panic!("expected ExpressionStatement, got {:?}", statement);
+                };
 
-                assert!(!expression_statement.expression.is_none());
-                let expression = expression_statement.expression.unwrap();
+                assert!(expression_statement.expression.is_some());
+                let expression = expression_statement.expression.as_ref().unwrap();
 
                 assert_eq!(expression.node_type(), NodeType::PrefixExpression);
-                let prefix_expression = to_concrete_expression::<PrefixExpression>(&expression);
+                let Expression::Prefix(prefix_expression) = expression else {
+                    panic!("expected PrefixExpression, got {:?}", expression);
+                };
 
                 assert_eq!(prefix_expression.operator, operator);
                 assert!(test_integer_literal(&prefix_expression.right, value));
@@ -407,7 +1455,7 @@ return 993322;");
             fn $name(){
                 let (input, left_value, operator, right_value) = $value;
 
-                let lexer = Lexer::new(String::from(input));
+                let lexer = Lexer::new(input);
                 let mut parser = Parser::new(lexer);
                 let program = parser.parse_program();
                 check_parser_errors(&parser);
@@ -415,13 +1463,17 @@ return 993322;");
 
                 let statement = &program.statements[0];
                 assert_eq!(statement.node_type(), NodeType::ExpressionStatement);
-                let expression_statement = to_concrete_statement::<ExpressionStatement>(statement);
+                let Statement::Expression(expression_statement) = statement else {
+                    panic!("expected ExpressionStatement, got {:?}", statement);
+                };
 
-                assert!(!expression_statement.expression.is_none());
-                let expression = expression_statement.expression.unwrap();
+                assert!(expression_statement.expression.is_some());
+                let expression = expression_statement.expression.as_ref().unwrap();
 
                 assert_eq!(expression.node_type(), NodeType::InfixExpression);
-                let infix_expression = to_concrete_expression::<InfixExpression>(&expression);
+                let Expression::Infix(infix_expression) = expression else {
+                    panic!("expected InfixExpression, got {:?}", expression);
+                };
 
                 assert!(test_integer_literal(&infix_expression.left, left_value));
                 assert_eq!(infix_expression.operator, operator);
@@ -448,7 +1500,7 @@ return 993322;");
             fn $name(){
                 let (input, expected) = $value;
 
-                let lexer = Lexer::new(String::from(input));
+                let lexer = Lexer::new(input);
                 let mut parser = Parser::new(lexer);
                 let program = parser.parse_program();
                 check_parser_errors(&parser);
@@ -474,5 +1526,287 @@ return 993322;");
         test_operator_precedence_parsing_11: ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4))")
         test_operator_precedence_parsing_12: ("3 + 4 * 5 == 3 * 1 + 4 * 5",
             "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))")
+        test_operator_precedence_parsing_13: ("true", "true")
+        test_operator_precedence_parsing_14: ("false", "false")
+        test_operator_precedence_parsing_15: ("3 > 5 == false", "((3 > 5) == false)")
+        test_operator_precedence_parsing_16: ("3 < 5 == true", "((3 < 5) == true)")
+        test_operator_precedence_parsing_17: ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)")
+        test_operator_precedence_parsing_18: ("(5 + 5) * 2", "((5 + 5) * 2)")
+        test_operator_precedence_parsing_19: ("2 / (5 + 5)", "(2 / (5 + 5))")
+        test_operator_precedence_parsing_20: ("-(5 + 5)", "(-(5 + 5))")
+        test_operator_precedence_parsing_21: ("!(true == true)", "(!(true == true))")
+        test_operator_precedence_parsing_22: ("a + add(b * c) + d", "((a + add((b * c))) + d)")
+        test_operator_precedence_parsing_23: ("add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
+            "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))")
+        test_operator_precedence_parsing_24: ("add(a + b + c * d / f + g)",
+            "add((((a + b) + ((c * d) / f)) + g))")
+        test_operator_precedence_parsing_25: ("fn(x) { x }(5)", "fn(x) { x }(5)")
+        test_operator_precedence_parsing_26: ("-f(x)", "(-f(x))")
+        test_operator_precedence_parsing_27: ("1 + 2.5 * .5", "(1 + (2.5 * .5))")
+        test_operator_precedence_parsing_28: ("-1.5 < 2 == 3e2 > 1", "(((-1.5) < 2) == (3e2 > 1))")
+        test_operator_precedence_parsing_29: ("a <= b == c >= d", "((a <= b) == (c >= d))")
+        test_operator_precedence_parsing_30: ("a + b % c * d", "(a + ((b % c) * d))")
+        test_operator_precedence_parsing_31: ("-a % b", "((-a) % b)")
+        test_operator_precedence_parsing_32: ("a == b && c != d", "((a == b) && (c != d))")
+        test_operator_precedence_parsing_33: ("a || b && c", "(a || (b && c))")
+        test_operator_precedence_parsing_34: ("a && b || c && d", "((a && b) || (c && d))")
+        test_operator_precedence_parsing_35: ("a || b || c", "((a || b) || c)")
+        test_operator_precedence_parsing_36: ("!a && b < c + 1", "((!a) && (b < (c + 1)))")
+        test_operator_precedence_parsing_37: ("(a || b) && f(c)", "((a || b) && f(c))")
+        test_operator_precedence_parsing_38: ("~a", "(~a)")
+        test_operator_precedence_parsing_39: ("~a & b", "((~a) & b)")
+        test_operator_precedence_parsing_40: ("a | b ^ c & d", "(a | (b ^ (c & d)))")
+        test_operator_precedence_parsing_41: ("a & b | c & d", "((a & b) | (c & d))")
+        test_operator_precedence_parsing_42: ("a << b + c", "(a << (b + c))")
+        test_operator_precedence_parsing_43: ("a & b << c", "(a & (b << c))")
+        test_operator_precedence_parsing_44: ("a >> b >> c", "((a >> b) >> c)")
+        test_operator_precedence_parsing_45: ("a & b == c", "((a & b) == c)")
+        test_operator_precedence_parsing_46: ("a | b < c ^ d", "((a | b) < (c ^ d))")
+        test_operator_precedence_parsing_47: ("a & b && c | d", "((a & b) && (c | d))")
+        test_operator_precedence_parsing_48: ("a * [1, 2, 3, 4][b * c] * d",
+            "((a * ([1, 2, 3, 4][(b * c)])) * d)")
+        test_operator_precedence_parsing_49: ("add(a * b[2], b[1], 2 * [1, 2][1])",
+            "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))")
+        test_operator_precedence_parsing_50: ("a[1 + 1]", "(a[(1 + 1)])")
+        test_operator_precedence_parsing_51: ("f()[0][1]", "((f()[0])[1])")
+        test_operator_precedence_parsing_52: ("-a[0]", "(-(a[0]))")
+        test_operator_precedence_parsing_53: ("~a[0] << [][1]", "((~(a[0])) << ([][1]))")
+        test_operator_precedence_parsing_54: ("a = b = c", "(a = (b = c))")
+        test_operator_precedence_parsing_55: ("a = b || c && d", "(a = (b || (c && d)))")
+        test_operator_precedence_parsing_56: ("a += b * c", "(a += (b * c))")
+        test_operator_precedence_parsing_57: ("a[i + 1] -= f(x)[0]", "((a[(i + 1)]) -= (f(x)[0]))")
+        test_operator_precedence_parsing_58: ("a *= b /= c", "(a *= (b /= c))")
+        test_operator_precedence_parsing_59: ("x = fn(y) { y = 1 }", "(x = fn(y) { (y = 1) })")
+    }
+
+    macro_rules! test_statement_span {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, start, end) = $value;
+
+                let lexer = Lexer::new(input);
+                let mut parser = Parser::new(lexer);
+                let program = parser.parse_program();
+                check_parser_errors(&parser);
+
+                let span = program.statements.last().unwrap().span();
+                assert_eq!((span.start.offset, span.start.line, span.start.column), start);
+                assert_eq!((span.end.offset, span.end.line, span.end.column), end);
+            }
+        )*
+        }
+    }
+
+    test_statement_span! {
+        test_statement_span_1: ("let x = 5;", (0, 1, 1), (9, 1, 10))
+        test_statement_span_2: ("return;", (0, 1, 1), (6, 1, 7))
+        test_statement_span_3: ("  -a * b", (2, 1, 3), (8, 1, 9))
+        test_statement_span_4: ("x;\nlet total = a +\n  bb;", (3, 2, 1), (23, 3, 5))
+        test_statement_span_5: ("[1, 22]", (0, 1, 1), (7, 1, 8))
+        test_statement_span_6: ("a[bc]", (0, 1, 1), (5, 1, 6))
+        test_statement_span_7: ("{a: bc}", (0, 1, 1), (7, 1, 8))
+        test_statement_span_8: ("f(a)", (0, 1, 1), (4, 1, 5))
+        test_statement_span_9: ("while (x) { y }", (0, 1, 1), (15, 1, 16))
+        test_statement_span_10: ("fn() {}", (0, 1, 1), (7, 1, 8))
+        test_statement_span_11: ("if (x) { y } else if (z) { w }", (0, 1, 1), (30, 1, 31))
+    }
+
+    fn parse_errors(input: &str) -> Vec<ParseError> {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+        parser.errors().to_vec()
+    }
+
+    #[test]
+    fn test_unexpected_token_error() {
+        assert_eq!(parse_errors("let x 5;"), vec![ParseError::UnexpectedToken {
+            expected: TokenType::Assign,
+            found: TokenType::Int,
+            span: Span::new(Position::new(6, 1, 7), Position::new(7, 1, 8)),
+        }]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_no_prefix_parse_function_error() {
+        assert_eq!(parse_errors("let x = ;"), vec![ParseError::NoPrefixParseFunction {
+            found: TokenType::Semicolon,
+            span: Span::new(Position::new(8, 1, 9), Position::new(9, 1, 10)),
+        }]);
+    }
+
+    #[test]
+    fn test_malformed_number_error() {
+        let error = LexError::MalformedNumber {
+            literal: String::from("1.2.3"),
+            span: Span::new(Position::new(8, 1, 9), Position::new(13, 1, 14)),
+        };
+        assert_eq!(parse_errors("let x = 1.2.3;")[0], ParseError::Lex(error));
+    }
+
+    #[test]
+    fn test_unterminated_grouped_expression_error() {
+        assert_eq!(parse_errors("(1 + 2"), vec![ParseError::Unterminated {
+            expected: TokenType::RParen,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(6, 1, 7), Position::new(6, 1, 7)),
+        }]);
+    }
+
+    #[test]
+    fn test_unterminated_block_error() {
+        assert_eq!(parse_errors("if (x) { x"), vec![ParseError::Unterminated {
+            expected: TokenType::RBrace,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(10, 1, 11), Position::new(10, 1, 11)),
+        }]);
+    }
+
+    #[test]
+    fn test_invalid_function_parameter_error() {
+        assert_eq!(parse_errors("fn(x, 1) { x }")[0], ParseError::UnexpectedToken {
+            expected: TokenType::Ident,
+            found: TokenType::Int,
+            span: Span::new(Position::new(6, 1, 7), Position::new(7, 1, 8)),
+        });
+    }
+
+    #[test]
+    fn test_unterminated_call_error() {
+        assert_eq!(parse_errors("add(1, 2"), vec![ParseError::Unterminated {
+            expected: TokenType::RParen,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(8, 1, 9), Position::new(8, 1, 9)),
+        }]);
+    }
+
+    #[test]
+    fn test_unterminated_array_error() {
+        assert_eq!(parse_errors("[1, 2"), vec![ParseError::Unterminated {
+            expected: TokenType::RBracket,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(5, 1, 6), Position::new(5, 1, 6)),
+        }]);
+    }
+
+    #[test]
+    fn test_unterminated_index_error() {
+        assert_eq!(parse_errors("a[1 2]")[0], ParseError::UnexpectedToken {
+            expected: TokenType::RBracket,
+            found: TokenType::Int,
+            span: Span::new(Position::new(4, 1, 5), Position::new(5, 1, 6)),
+        });
+    }
+
+    #[test]
+    fn test_hash_missing_colon_error() {
+        assert_eq!(parse_errors("{\"a\" 1}")[0], ParseError::UnexpectedToken {
+            expected: TokenType::Colon,
+            found: TokenType::Int,
+            span: Span::new(Position::new(5, 1, 6), Position::new(6, 1, 7)),
+        });
+    }
+
+    #[test]
+    fn test_hash_missing_comma_error() {
+        assert_eq!(parse_errors("{a: 1 b: 2}")[0], ParseError::UnexpectedToken {
+            expected: TokenType::Comma,
+            found: TokenType::Ident,
+            span: Span::new(Position::new(6, 1, 7), Position::new(7, 1, 8)),
+        });
+    }
+
+    #[test]
+    fn test_unterminated_hash_error() {
+        assert_eq!(parse_errors("{a: 1"), vec![ParseError::Unterminated {
+            expected: TokenType::RBrace,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(5, 1, 6), Position::new(5, 1, 6)),
+        }]);
+        assert_eq!(parse_errors("{a: 1,"), vec![ParseError::Unterminated {
+            expected: TokenType::RBrace,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(6, 1, 7), Position::new(6, 1, 7)),
+        }]);
+        assert_eq!(parse_errors("{"), vec![ParseError::Unterminated {
+            expected: TokenType::RBrace,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(1, 1, 2), Position::new(1, 1, 2)),
+        }]);
+    }
+
+    macro_rules! test_outside_loop_error {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, keyword, start, end) = $value;
+                assert_eq!(parse_errors(input), vec![ParseError::OutsideLoop {
+                    keyword: String::from(keyword),
+                    span: Span::new(Position::new(start, 1, start + 1),
+                                    Position::new(end, 1, end + 1)),
+                }]);
+            }
+        )*
+        }
+    }
+
+    test_outside_loop_error! {
+        test_outside_loop_error_1: ("break;", "break", 0, 5)
+        test_outside_loop_error_2: ("if (x) { continue }", "continue", 9, 17)
+        test_outside_loop_error_3: ("while (x) { fn() { break; } }", "break", 19, 24)
+        test_outside_loop_error_4: ("for x in y { }; continue;", "continue", 16, 24)
+    }
+
+    #[test]
+    fn test_for_statement_missing_in_error() {
+        assert_eq!(parse_errors("for x y { }")[0], ParseError::UnexpectedToken {
+            expected: TokenType::In,
+            found: TokenType::Ident,
+            span: Span::new(Position::new(6, 1, 7), Position::new(7, 1, 8)),
+        });
+    }
+
+    macro_rules! test_invalid_assignment_target_error {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, target, start, end) = $value;
+                assert_eq!(parse_errors(input)[0], ParseError::InvalidAssignmentTarget {
+                    target: String::from(target),
+                    span: Span::new(Position::new(start, 1, start + 1),
+                                    Position::new(end, 1, end + 1)),
+                });
+            }
+        )*
+        }
+    }
+
+    test_invalid_assignment_target_error! {
+        test_invalid_assignment_target_error_1: ("1 = 2", "1", 0, 1)
+        test_invalid_assignment_target_error_2: ("f() = 3", "f()", 0, 3)
+        test_invalid_assignment_target_error_3: ("a + b = c", "(a + b)", 0, 5)
+        test_invalid_assignment_target_error_4: ("f()[0] += 1", "(f()[0])", 0, 6)
+        test_invalid_assignment_target_error_5: ("let x = [1][0] = 2;", "([1][0])", 8, 14)
+    }
+
+    #[test]
+    fn test_lexer_error() {
+        let error = LexError::UnterminatedString {
+            span: Span::new(Position::new(8, 1, 9), Position::new(13, 1, 14)),
+        };
+        assert_eq!(parse_errors("let s = \"abc;"), vec![ParseError::Lex(error)]);
+    }
+
+    #[test]
+    fn test_unterminated_error() {
+        assert_eq!(parse_errors("let x"), vec![ParseError::Unterminated {
+            expected: TokenType::Assign,
+            found: TokenType::EOF,
+            span: Span::new(Position::new(5, 1, 6), Position::new(5, 1, 6)),
+        }]);
+    }
+}
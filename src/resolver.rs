@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use crate::ast::{BlockStatement, Expression, FunctionLiteral, Identifier, Program, Statement};
+use crate::error::ResolveError;
+use crate::token::Span;
+
+#[derive(Debug, Clone, Copy)]
+enum Binding {
+    Mutable,
+    /// Holds the span of the name in the `const` statement.
+    Const(Span),
+}
+
+/// Checks a parsed program for assignments to const bindings before it is run.
+///
+/// Scopes follow the evaluator: each function body is a scope of its own, while the blocks of
+/// `if`, `while` and `for` share the scope around them. The outermost scope is kept between
+/// calls to `resolve`, so a REPL can check each line against the consts declared before it.
+///
+/// A function looks its names up when it is called, which may be after a const is declared
+/// further down the enclosing scope. Function bodies are therefore resolved once the rest of
+/// that scope has been, so they see every declaration in it.
+#[derive(Debug)]
+pub struct Resolver {
+    scopes: Vec<HashMap<String, Binding>>,
+    errors: Vec<ResolveError>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns every error in `program`. The declarations in a program with errors are
+    /// forgotten, since it will not be run.
+    pub fn resolve(&mut self, program: &Program) -> Vec<ResolveError> {
+        let globals = self.scopes[0].clone();
+        let mut functions = Vec::new();
+        for statement in &program.statements {
+            self.resolve_statement(statement, &mut functions);
+        }
+        self.resolve_functions(functions);
+        let errors = std::mem::take(&mut self.errors);
+        if !errors.is_empty() {
+            self.scopes[0] = globals;
+        }
+        errors
+    }
+
+    /// Function literals found in `statement` are added to `functions` rather than resolved.
+    fn resolve_statement<'a>(&mut self, statement: &'a Statement<'a>,
+                             functions: &mut Vec<&'a FunctionLiteral<'a>>) {
+        match statement {
+            // The name is declared before the value is resolved, because a function in the
+            // value can only run once the binding exists.
+            Statement::Let(let_statement) => {
+                self.declare(&let_statement.name, Binding::Mutable);
+                self.resolve_expression(&let_statement.value, functions);
+            }
+            Statement::Const(const_statement) => {
+                let declared = const_statement.name.token.span;
+                self.declare(&const_statement.name, Binding::Const(declared));
+                self.resolve_expression(&const_statement.value, functions);
+            }
+            Statement::Return(return_statement) => {
+                if let Some(value) = &return_statement.value {
+                    self.resolve_expression(value, functions);
+                }
+            }
+            Statement::Expression(expression_statement) => {
+                if let Some(expression) = &expression_statement.expression {
+                    self.resolve_expression(expression, functions);
+                }
+            }
+            Statement::While(while_statement) => {
+                self.resolve_expression(&while_statement.condition, functions);
+                self.resolve_block(&while_statement.body, functions);
+            }
+            Statement::For(for_statement) => {
+                self.resolve_expression(&for_statement.iterable, functions);
+                self.declare(&for_statement.variable, Binding::Mutable);
+                self.resolve_block(&for_statement.body, functions);
+            }
+            Statement::Break(_) | Statement::Continue(_) => {}
+        }
+    }
+
+    fn resolve_block<'a>(&mut self, block: &'a BlockStatement<'a>,
+                         functions: &mut Vec<&'a FunctionLiteral<'a>>) {
+        for statement in &block.statements {
+            self.resolve_statement(statement, functions);
+        }
+    }
+
+    /// Resolves each function body in a scope of its own, nested in the current one.
+    fn resolve_functions(&mut self, functions: Vec<&FunctionLiteral>) {
+        for function_literal in functions {
+            self.scopes.push(HashMap::new());
+            for parameter in &function_literal.parameters {
+                self.declare(parameter, Binding::Mutable);
+            }
+            let mut nested = Vec::new();
+            self.resolve_block(&function_literal.body, &mut nested);
+            self.resolve_functions(nested);
+            self.scopes.pop();
+        }
+    }
+
+    fn resolve_expression<'a>(&mut self, expression: &'a Expression<'a>,
+                              functions: &mut Vec<&'a FunctionLiteral<'a>>) {
+        match expression {
+            Expression::Identifier(_) |
+            Expression::IntegerLiteral(_) |
+            Expression::BigIntegerLiteral(_) |
+            Expression::FloatLiteral(_) |
+            Expression::BooleanLiteral(_) |
+            Expression::StringLiteral(_) => {}
+            Expression::Prefix(prefix_expression) => {
+                self.resolve_expression(&prefix_expression.right, functions);
+            }
+            Expression::Infix(infix_expression) => {
+                self.resolve_expression(&infix_expression.left, functions);
+                self.resolve_expression(&infix_expression.right, functions);
+            }
+            Expression::Logical(logical_expression) => {
+                self.resolve_expression(&logical_expression.left, functions);
+                self.resolve_expression(&logical_expression.right, functions);
+            }
+            Expression::If(if_expression) => {
+                self.resolve_expression(&if_expression.condition, functions);
+                self.resolve_block(&if_expression.consequence, functions);
+                if let Some(alternative) = &if_expression.alternative {
+                    self.resolve_block(alternative, functions);
+                }
+            }
+            Expression::FunctionLiteral(function_literal) => functions.push(function_literal),
+            Expression::Call(call_expression) => {
+                self.resolve_expression(&call_expression.function, functions);
+                for argument in &call_expression.arguments {
+                    self.resolve_expression(argument, functions);
+                }
+            }
+            Expression::ArrayLiteral(array_literal) => {
+                for element in &array_literal.elements {
+                    self.resolve_expression(element, functions);
+                }
+            }
+            Expression::Index(index_expression) => {
+                self.resolve_expression(&index_expression.left, functions);
+                self.resolve_expression(&index_expression.index, functions);
+            }
+            Expression::HashLiteral(hash_literal) => {
+                for (key, value) in &hash_literal.pairs {
+                    self.resolve_expression(key, functions);
+                    self.resolve_expression(value, functions);
+                }
+            }
+            Expression::Assign(assign_expression) => {
+                self.resolve_expression(&assign_expression.target, functions);
+                self.resolve_expression(&assign_expression.value, functions);
+                // Assigning through an index rebinds the name the target starts from.
+                let mut root = assign_expression.target.as_ref();
+                while let Expression::Index(index_expression) = root {
+                    root = &index_expression.left;
+                }
+                if let Expression::Identifier(identifier) = root {
+                    if let Some(Binding::Const(declared)) = self.lookup(&identifier.value) {
+                        self.errors.push(ResolveError::ConstReassignment {
+                            name: identifier.value.to_string(),
+                            span: identifier.token.span,
+                            declared,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Binds `name` in the innermost scope. Replacing a const declared in the same scope would
+    /// change its value, so that is reported as a reassignment.
+    fn declare(&mut self, name: &Identifier, binding: Binding) {
+        let scope = self.scopes.last_mut().unwrap();
+        if let Some(Binding::Const(declared)) = scope.get(name.value.as_ref()) {
+            self.errors.push(ResolveError::ConstReassignment {
+                name: name.value.to_string(),
+                span: name.token.span,
+                declared: *declared,
+            });
+            return;
+        }
+        scope.insert(name.value.to_string(), binding);
+    }
+
+    fn lookup(&self, name: &str) -> Option<Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ResolveError;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+
+    fn resolve(input: &str) -> Vec<ResolveError> {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "parser errors: {:?}", parser.errors());
+        Resolver::new().resolve(&program)
+    }
+
+    macro_rules! test_resolve_errors {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let (input, expected): (&str, &[(&str, usize, usize, usize)]) = $value;
+                let errors: Vec<(String, usize, usize, usize)> = resolve(input).into_iter()
+                    .map(|error| match error {
+                        ResolveError::ConstReassignment { name, span, declared } => {
+                            (name, span.start.offset, span.end.offset, declared.start.offset)
+                        }
+                    })
+                    .collect();
+                let expected: Vec<(String, usize, usize, usize)> = expected.iter()
+                    .map(|(name, start, end, declared)| (name.to_string(), *start, *end, *declared))
+                    .collect();
+                assert_eq!(errors, expected);
+            }
+        )*
+        }
+    }
+
+    test_resolve_errors! {
+        test_resolve_errors_1: ("const x = 1; x", &[])
+        test_resolve_errors_2: ("const x = 1; x = 2;", &[("x", 13, 14, 6)])
+        test_resolve_errors_3: ("const x = 1; x += 2; x -= 3;",
+            &[("x", 13, 14, 6), ("x", 21, 22, 6)])
+        test_resolve_errors_4: ("const xs = [1]; xs[0] = 2;", &[("xs", 16, 18, 6)])
+        test_resolve_errors_5: ("const x = 1; let x = 2;", &[("x", 17, 18, 6)])
+        test_resolve_errors_6: ("const x = 1; const x = 2;", &[("x", 19, 20, 6)])
+        test_resolve_errors_7: ("const x = 1; for x in [1] { }", &[("x", 17, 18, 6)])
+        test_resolve_errors_8: ("const n = 0; let f = fn() { n = 1 };", &[("n", 28, 29, 6)])
+        test_resolve_errors_9: ("const n = 0; while (true) { if (n) { n = 1 } }",
+            &[("n", 37, 38, 6)])
+        test_resolve_errors_10: ("const f = fn() { f = 1 };", &[("f", 17, 18, 6)])
+        test_resolve_errors_11: ("const n = 0; let f = fn(n) { n = 1 };", &[])
+        test_resolve_errors_12: ("const n = 0; let f = fn() { let n = 1; n = 2 };", &[])
+        test_resolve_errors_13: ("let x = 1; const x = 2; x = 3;", &[("x", 24, 25, 17)])
+        test_resolve_errors_14: ("let x = 1; x = 2; let x = 3;", &[])
+        test_resolve_errors_15: ("const a = 1; let b = [a]; b[0] = a;", &[])
+        test_resolve_errors_16: ("let f = fn() { n = 1 }; const n = 0; f();", &[("n", 15, 16, 30)])
+        test_resolve_errors_17: ("let f = fn() { let g = fn() { n = 5 }; g }; const n = 1;",
+            &[("n", 30, 31, 50)])
+        test_resolve_errors_18: ("let f = fn() { n = 1 }; let n = 0; f();", &[])
+    }
+
+    #[test]
+    fn test_resolver_keeps_globals() {
+        let mut resolver = Resolver::new();
+        let parse = |input| Parser::new(Lexer::new(input)).parse_program();
+
+        assert!(resolver.resolve(&parse("const limit = 10; let bad = 1;")).is_empty());
+        assert_eq!(resolver.resolve(&parse("limit = 11;")).len(), 1);
+        // Declarations in a program with errors are dropped.
+        assert_eq!(resolver.resolve(&parse("const bad = 2; limit = 12;")).len(), 1);
+        assert!(resolver.resolve(&parse("bad = 3;")).is_empty());
+    }
+}
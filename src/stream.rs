@@ -0,0 +1,315 @@
+use std::io::{BufRead, ErrorKind};
+use std::iter::FusedIterator;
+use crate::error::LexError;
+use crate::lexer::{Lexer, TokenSource};
+use crate::token::{Position, Span, Token, TokenType};
+
+/// A lexer over any `BufRead` source that reads the input a chunk at a time, as the reader's own
+/// buffer fills, so large files and piped input are never held in memory at once. Tokens own
+/// their literals, and spans are positions in the whole stream rather than in the current buffer.
+pub struct StreamLexer<R> {
+    reader: R,
+    /// Input that has been read but not fully lexed. Everything before `consumed` has already
+    /// been returned as tokens.
+    buffer: String,
+    consumed: usize,
+    /// Bytes read after the last complete character, such as the first half of a character that
+    /// was split between two reads.
+    partial: Vec<u8>,
+    /// The position of `buffer[consumed]` in the stream.
+    base: Position,
+    exhausted: bool,
+    /// The message of a read that failed. It is reported where the readable input ends.
+    failure: Option<String>,
+    errors: Vec<LexError>,
+    keep_doc_comments: bool,
+    keep_trivia: bool,
+    finished: bool,
+}
+
+impl<R: BufRead> StreamLexer<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: String::new(),
+            consumed: 0,
+            partial: Vec::new(),
+            base: Position::new(0, 1, 1),
+            exhausted: false,
+            failure: None,
+            errors: Vec::new(),
+            keep_doc_comments: false,
+            keep_trivia: false,
+            finished: false,
+        }
+    }
+
+    /// See `Lexer::with_doc_comments`.
+    pub fn with_doc_comments(mut self) -> Self {
+        self.keep_doc_comments = true;
+        self
+    }
+
+    /// See `Lexer::with_trivia`.
+    pub fn with_trivia(mut self) -> Self {
+        self.keep_trivia = true;
+        self
+    }
+
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Removes and returns the errors reported since the last call.
+    pub fn take_errors(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    pub fn next_token(&mut self) -> Token<'static> {
+        loop {
+            let remaining = &self.buffer[self.consumed..];
+            let mut lexer = Lexer::new(remaining).starting_at(self.base);
+            if self.keep_doc_comments {
+                lexer = lexer.with_doc_comments();
+            }
+            if self.keep_trivia {
+                lexer = lexer.with_trivia();
+            }
+            let token = lexer.next_token();
+            let end = lexer.current_position();
+            let length = end.offset - self.base.offset;
+
+            // The lexer looks at the character after a token and the one after that, for
+            // example to tell `/` from the start of a comment in trailing trivia. A token that
+            // ends closer than that to the end of the buffer may read differently once more
+            // input arrives, so it is lexed again then. Waiting until the unlexed input has
+            // doubled keeps the total work linear for tokens that span many reads.
+            if remaining[length..].chars().nth(1).is_some() || self.exhausted {
+                let token = token.into_owned();
+                self.errors.extend(lexer.take_errors());
+                if token.token_type == TokenType::EOF {
+                    if let Some(message) = self.failure.take() {
+                        self.errors.push(LexError::Io { message, span: Span::new(end, end) });
+                    }
+                }
+                self.consumed += length;
+                self.base = end;
+                return token;
+            }
+            let wanted = remaining.len() * 2 + 1;
+            self.fill_buffer(wanted);
+        }
+    }
+
+    /// Reads chunks until the buffer holds at least `wanted` bytes of unlexed input or the
+    /// input runs out, dropping the text already lexed first.
+    fn fill_buffer(&mut self, wanted: usize) {
+        self.buffer.drain(..self.consumed);
+        self.consumed = 0;
+        while self.buffer.len() < wanted && !self.exhausted {
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.fail(error.to_string());
+                    return;
+                }
+            };
+            if chunk.is_empty() {
+                if self.partial.is_empty() {
+                    self.exhausted = true;
+                } else {
+                    self.fail(String::from("stream did not contain valid UTF-8"));
+                }
+                return;
+            }
+            self.partial.extend_from_slice(chunk);
+            let length = chunk.len();
+            self.reader.consume(length);
+            self.decode_partial();
+        }
+    }
+
+    /// Moves the complete characters at the start of `partial` into the buffer.
+    fn decode_partial(&mut self) {
+        let (valid, invalid) = match std::str::from_utf8(&self.partial) {
+            Ok(_) => (self.partial.len(), false),
+            Err(error) => (error.valid_up_to(), error.error_len().is_some()),
+        };
+        self.buffer.push_str(std::str::from_utf8(&self.partial[..valid]).unwrap());
+        self.partial.drain(..valid);
+        if invalid {
+            self.fail(String::from("stream did not contain valid UTF-8"));
+        }
+    }
+
+    fn fail(&mut self, message: String) {
+        self.failure = Some(message);
+        self.exhausted = true;
+    }
+}
+
+impl<R: BufRead> TokenSource<'static> for StreamLexer<R> {
+    fn next_token(&mut self) -> Token<'static> {
+        StreamLexer::next_token(self)
+    }
+
+    fn take_errors(&mut self) -> Vec<LexError> {
+        StreamLexer::take_errors(self)
+    }
+}
+
+/// Yields every token up to and including `EOF`, which is yielded exactly once.
+impl<R: BufRead> Iterator for StreamLexer<R> {
+    type Item = Token<'static>;
+
+    fn next(&mut self) -> Option<Token<'static>> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = token.token_type == TokenType::EOF;
+        Some(token)
+    }
+}
+
+impl<R: BufRead> FusedIterator for StreamLexer<R> {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+    use crate::ast::{Node, Statement};
+    use crate::error::LexError;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::stream::StreamLexer;
+    use crate::token::{Position, Span, TokenType};
+
+    /// Returns one, two or three bytes per read in turn, so characters, strings and comments
+    /// are split between reads.
+    struct Trickle<'a> {
+        input: &'a [u8],
+        reads: usize,
+    }
+
+    impl<'a> Trickle<'a> {
+        fn new(input: &'a str) -> Self {
+            Self { input: input.as_bytes(), reads: 0 }
+        }
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let length = (self.reads % 3 + 1).min(buf.len()).min(self.input.len());
+            self.reads += 1;
+            buf[..length].copy_from_slice(&self.input[..length]);
+            self.input = &self.input[length..];
+            Ok(length)
+        }
+    }
+
+    macro_rules! test_stream_matches_lexer {
+        ($($name:ident: $value:expr)*) => {
+        $(
+            #[test]
+            fn $name(){
+                let input: &str = $value;
+
+                let mut expected_lexer = Lexer::new(input).with_trivia();
+                let expected: Vec<_> = expected_lexer.by_ref().collect();
+                let mut stream = StreamLexer::new(BufReader::new(Trickle::new(input)))
+                    .with_trivia();
+                let actual: Vec<_> = stream.by_ref().collect();
+
+                assert_eq!(actual.len(), expected.len());
+                for (actual, expected) in actual.iter().zip(&expected) {
+                    assert_eq!(actual.token_type, expected.token_type);
+                    assert_eq!(actual.literal, expected.literal);
+                    assert_eq!(actual.span, expected.span);
+                    assert_eq!(actual.leading_trivia, expected.leading_trivia);
+                    assert_eq!(actual.trailing_trivia, expected.trailing_trivia);
+                }
+                assert_eq!(stream.errors(), expected_lexer.errors());
+            }
+        )*
+        }
+    }
+
+    test_stream_matches_lexer! {
+        test_stream_matches_lexer_1: ""
+        test_stream_matches_lexer_2: "let x = 5;\nlet y = x + 10;\n"
+        test_stream_matches_lexer_3: "let s = \"one\ntwo\\n\nthree\";\nlen(s)"
+        test_stream_matches_lexer_4: "1 /* a\n/* nested\n*/ still\n*/ + 2 // end\n\n\n"
+        test_stream_matches_lexer_5: "let größe = 1;\r\n日本 == größe;\r\n"
+        test_stream_matches_lexer_6: "let a = @;\nlet s = \"never\nclosed"
+        test_stream_matches_lexer_7: "x /* open\nforever"
+        test_stream_matches_lexer_8: "\"größe 日本 🦀\" /* ß 日 */ 🦀; // 本\n"
+        test_stream_matches_lexer_9: "let name = \"a string longer than several reads\";"
+    }
+
+    #[test]
+    fn test_stream_drops_lexed_input() {
+        let input = "let x = [1, 2, 3];\n".repeat(1000);
+        let mut stream = StreamLexer::new(BufReader::new(Trickle::new(&input)));
+
+        while stream.next_token().token_type != TokenType::EOF {
+            assert!(stream.buffer.len() < 16, "buffered {:?}", stream.buffer);
+        }
+        assert!(stream.errors().is_empty());
+    }
+
+    #[test]
+    fn test_stream_split_character_at_end() {
+        let input: &[u8] = b"x \xc3";
+        let mut stream = StreamLexer::new(input);
+
+        let types: Vec<_> = stream.by_ref().map(|token| token.token_type).collect();
+        assert_eq!(types, vec![TokenType::Ident, TokenType::EOF]);
+        assert_eq!(stream.errors(), &[LexError::Io {
+            message: String::from("stream did not contain valid UTF-8"),
+            span: Span::new(Position::new(2, 1, 3), Position::new(2, 1, 3)),
+        }]);
+    }
+
+    #[test]
+    fn test_parse_stream() {
+        let input = "/// Adds.\nlet add = fn(a, b) {\n  a + b\n};\n\nadd(1,\n    2);\n";
+
+        let mut parser = Parser::new(Lexer::new(input).with_doc_comments());
+        let expected = parser.parse_program();
+        let mut parser = Parser::new(StreamLexer::new(input.as_bytes()).with_doc_comments());
+        let program = parser.parse_program();
+
+        assert!(parser.errors().is_empty(), "parser errors: {:?}", parser.errors());
+        assert_eq!(program.to_string(), expected.to_string());
+        let spans: Vec<_> = program.statements.iter().map(|statement| statement.span()).collect();
+        let expected_spans: Vec<_> = expected.statements.iter()
+            .map(|statement| statement.span())
+            .collect();
+        assert_eq!(spans, expected_spans);
+        let Statement::Let(statement) = &program.statements[0] else {
+            panic!("expected a let statement");
+        };
+        assert_eq!(statement.doc.as_deref(), Some("Adds."));
+    }
+
+    #[test]
+    fn test_read_error() {
+        let input: &[u8] = b"let x = 1;\n\xff\n";
+        let mut stream = StreamLexer::new(input);
+
+        let types: Vec<_> = stream.by_ref().map(|token| token.token_type).collect();
+        assert_eq!(types, vec![
+            TokenType::Let,
+            TokenType::Ident,
+            TokenType::Assign,
+            TokenType::Int,
+            TokenType::Semicolon,
+            TokenType::EOF,
+        ]);
+        assert_eq!(stream.errors(), &[LexError::Io {
+            message: String::from("stream did not contain valid UTF-8"),
+            span: Span::new(Position::new(11, 2, 1), Position::new(11, 2, 1)),
+        }]);
+    }
+}
@@ -1,3 +1,6 @@
+use std::borrow::Cow;
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Debug, Clone, Copy, Eq, Hash)]
 pub enum TokenType {
     Illegal,
@@ -6,6 +9,9 @@ pub enum TokenType {
     // Identifiers and literals
     Ident,
     Int,
+    Float,
+    String,
+    DocComment,
 
     // Operators
     Assign,
@@ -14,27 +20,54 @@ pub enum TokenType {
     Bang,
     Asterisk,
     Slash,
+    Percent,
+
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
 
     LT,
     GT,
+    LTEQ,
+    GTEQ,
+
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
+
+    And,
+    Or,
 
     // Delimiters
     Comma,
     Semicolon,
+    Colon,
 
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 
     // Keywords
     Function,
     Let,
+    Const,
     True,
     False,
     If,
     Else,
     Return,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
 
     EQ,
     NotEQ,
@@ -48,6 +81,9 @@ impl TokenType {
 
             TokenType::Ident => "IDENT",
             TokenType::Int => "INT",
+            TokenType::Float => "FLOAT",
+            TokenType::String => "STRING",
+            TokenType::DocComment => "DOC_COMMENT",
 
             TokenType::Assign => "=",
             TokenType::Plus => "+",
@@ -55,56 +91,199 @@ impl TokenType {
             TokenType::Bang => "!",
             TokenType::Asterisk => "*",
             TokenType::Slash => "/",
+            TokenType::Percent => "%",
+
+            TokenType::PlusAssign => "+=",
+            TokenType::MinusAssign => "-=",
+            TokenType::AsteriskAssign => "*=",
+            TokenType::SlashAssign => "/=",
 
             TokenType::LT => "<",
             TokenType::GT => ">",
+            TokenType::LTEQ => "<=",
+            TokenType::GTEQ => ">=",
+
+            TokenType::Ampersand => "&",
+            TokenType::Pipe => "|",
+            TokenType::Caret => "^",
+            TokenType::Tilde => "~",
+            TokenType::ShiftLeft => "<<",
+            TokenType::ShiftRight => ">>",
+
+            TokenType::And => "&&",
+            TokenType::Or => "||",
 
             TokenType::Comma => ",",
             TokenType::Semicolon => ";",
+            TokenType::Colon => ":",
 
             TokenType::LParen => "(",
             TokenType::RParen => ")",
             TokenType::LBrace => "{",
             TokenType::RBrace => "}",
+            TokenType::LBracket => "[",
+            TokenType::RBracket => "]",
 
             TokenType::Function => "FUNCTION",
             TokenType::Let => "LET",
+            TokenType::Const => "CONST",
             TokenType::True => "TRUE",
             TokenType::False => "FALSE",
             TokenType::If => "IF",
             TokenType::Else => "ELSE",
             TokenType::Return => "RETURN",
+            TokenType::While => "WHILE",
+            TokenType::For => "FOR",
+            TokenType::In => "IN",
+            TokenType::Break => "BREAK",
+            TokenType::Continue => "CONTINUE",
 
             TokenType::EQ => "==",
             TokenType::NotEQ => "!=",
         }
     }
 
-    pub fn lookup_identifier(identifier: &String) -> TokenType {
-        match identifier.as_str() {
+    pub fn lookup_identifier(identifier: &str) -> TokenType {
+        match identifier {
             "fn" => TokenType::Function,
             "let" => TokenType::Let,
+            "const" => TokenType::Const,
             "true" => TokenType::True,
             "false" => TokenType::False,
             "if" => TokenType::If,
             "else" => TokenType::Else,
             "return" => TokenType::Return,
+            "while" => TokenType::While,
+            "for" => TokenType::For,
+            "in" => TokenType::In,
+            "break" => TokenType::Break,
+            "continue" => TokenType::Continue,
             _ => TokenType::Ident
         }
     }
 }
 
+/// Splits an integer literal into its radix and digits, leaving any `_` separators in place.
+pub fn integer_radix(literal: &str) -> (u32, &str) {
+    match literal.get(..2) {
+        Some("0x") => (16, &literal[2..]),
+        Some("0o") => (8, &literal[2..]),
+        Some("0b") => (2, &literal[2..]),
+        _ => (10, literal),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+/// A half-open region of source text, `start` inclusive and `end` exclusive. Offsets are in
+/// bytes; lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self {
+            start,
+            end,
+        }
+    }
+
+    /// Returns a span running from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    LineComment,
+    BlockComment,
+    DocComment,
+}
+
+/// Source text between tokens that does not affect the meaning of the program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: Cow<'src, str>,
+    pub span: Span,
+}
+
+impl Trivia<'_> {
+    pub fn into_owned(self) -> Trivia<'static> {
+        Trivia {
+            kind: self.kind,
+            text: Cow::Owned(self.text.into_owned()),
+            span: self.span,
+        }
+    }
+}
+
+/// A token whose literal borrows from the source text where it can. Use `into_owned` to keep a
+/// token around after the source has gone.
 #[derive(Debug, Clone)]
-pub struct Token {
+pub struct Token<'src> {
     pub token_type: TokenType,
-    pub literal: String,
+    pub literal: Cow<'src, str>,
+    pub span: Span,
+    /// Trivia before the token, starting after the previous token's trailing trivia. Only
+    /// filled in by a lexer created with `Lexer::with_trivia`.
+    pub leading_trivia: Vec<Trivia<'src>>,
+    /// Trivia after the token up to, but not including, the next newline.
+    pub trailing_trivia: Vec<Trivia<'src>>,
 }
 
-impl Token {
-    pub fn new(token_type: TokenType, literal: String) -> Self {
+impl<'src> Token<'src> {
+    pub fn new(token_type: TokenType, literal: impl Into<Cow<'src, str>>) -> Self {
+        Self::with_span(token_type, literal, Span::default())
+    }
+
+    pub fn with_span(token_type: TokenType, literal: impl Into<Cow<'src, str>>,
+                     span: Span) -> Self {
         Self {
             token_type,
-            literal,
+            literal: literal.into(),
+            span,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
         }
     }
-}
\ No newline at end of file
+
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            token_type: self.token_type,
+            literal: Cow::Owned(self.literal.into_owned()),
+            span: self.span,
+            leading_trivia: self.leading_trivia.into_iter().map(Trivia::into_owned).collect(),
+            trailing_trivia: self.trailing_trivia.into_iter().map(Trivia::into_owned).collect(),
+        }
+    }
+}